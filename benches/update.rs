@@ -0,0 +1,117 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use spatial_neighbors::SpatialPartitioner;
+
+use boids::boid::Boid;
+use boids::sim_params::SimParams;
+use boids::simulation::Simulation;
+use boids::spatial::UniformGrid;
+use boids::steering::{compute_steering, default_behaviors};
+use boids::vec2::Vec2;
+
+const SEED: u64 = 42;
+const BOID_COUNTS: [usize; 3] = [1000, 5000, 20000];
+const INDEX_CAPACITIES: [u16; 5] = [10, 25, 50, 75, 150];
+/// around [DEFAULT_PARALLEL_THRESHOLD](boids::simulation::DEFAULT_PARALLEL_THRESHOLD), to see where the threads start paying off
+const THRESHOLD_BOID_COUNTS: [usize; 5] = [500, 1000, 2000, 4000, 8000];
+const THRESHOLD_THREADS: usize = 4;
+
+/// a simulation which already had some time to spread out from the spawn point
+fn warmed_up(boid_count: usize) -> Simulation {
+    let mut simulation = Simulation::new(boid_count, SimParams::default(), SEED);
+
+    for _ in 0..100 {
+        simulation.update();
+    }
+
+    simulation
+}
+
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+
+    for boid_count in BOID_COUNTS {
+        let mut simulation = warmed_up(boid_count);
+
+        group.bench_function(BenchmarkId::from_parameter(boid_count), |b| b.iter(|| simulation.update()));
+    }
+
+    group.finish();
+}
+
+fn index_capacity(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index_capacity");
+
+    for capacity in INDEX_CAPACITIES {
+        // a fresh simulation per capacity, a longer running flock clusters more and would skew the later ones
+        let mut simulation = warmed_up(20000);
+        simulation.index_capacity = capacity;
+
+        group.bench_function(BenchmarkId::from_parameter(capacity), |b| b.iter(|| simulation.update()));
+    }
+
+    group.finish();
+}
+
+fn parallel_threshold(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel_threshold");
+
+    for boid_count in THRESHOLD_BOID_COUNTS {
+        let mut sequential = warmed_up(boid_count);
+        sequential.thread_count = 1;
+
+        let mut threaded = warmed_up(boid_count);
+        threaded.parallel_threshold = 0;
+        // fixed so results from different machines compare, with a single core this only measures the overhead
+        threaded.thread_count = THRESHOLD_THREADS;
+
+        group.bench_function(BenchmarkId::new("sequential", boid_count), |b| b.iter(|| sequential.update()));
+        group.bench_function(BenchmarkId::new("threaded", boid_count), |b| b.iter(|| threaded.update()));
+    }
+
+    group.finish();
+}
+
+fn steering(c: &mut Criterion) {
+    let params = SimParams::default();
+    let behaviors = default_behaviors();
+
+    let boid = Boid::new(Vec2::ZERO, Vec2::UNIT_X);
+    let neighbors: Vec<Boid> = (0..50)
+        .map(|i| {
+            let mut location = Vec2::from_angle(i as f32);
+            location.mul(params.perception_radius * (i as f32 / 50.0));
+
+            Boid::new(location, Vec2::from_angle(i as f32 * 0.5))
+        })
+        .collect();
+    let neighbors: Vec<&Boid> = neighbors.iter().collect();
+    let neighbor_indices: Vec<usize> = (1..=neighbors.len()).collect();
+
+    c.bench_function("compute_steering/50", |b| b.iter(|| compute_steering(&boid, 0, &neighbors, &neighbor_indices, &params, &behaviors)));
+}
+
+fn index_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index_build");
+
+    let simulation = warmed_up(20000);
+    let (range_x, range_y) = simulation.params.index_range();
+
+    // cells about the size of the perception radius
+    let cell_count = ((range_x.end - range_x.start) / simulation.params.perception_radius as f64).ceil() as usize;
+
+    group.bench_function("quad_tree", |b| b.iter(|| simulation.build_index()));
+    group.bench_function("grid", |b| b.iter(|| {
+        let mut grid = UniformGrid::with_cell_count(range_x.clone(), range_y.clone(), (cell_count, cell_count));
+
+        for (index, boid) in simulation.boids().iter().enumerate() {
+            grid.insert((boid.location.x as f64, boid.location.y as f64), index);
+        }
+
+        grid
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, update, index_capacity, parallel_threshold, steering, index_build);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+use std::f32::consts::PI;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::boundary::Polygon;
+use crate::mesh::DEFAULT_MAX_VERTICES;
+use crate::sim_params::{SpawnPattern, SpawnSpeed, MAX_PERCEPTION_RADIUS, MIN_PERCEPTION_RADIUS};
+use crate::trails::{DEFAULT_TRAIL_LENGTH, MAX_TRAIL_LENGTH};
+use crate::simulation::{DEFAULT_INDEX_CAPACITY, DEFAULT_PARALLEL_THRESHOLD};
+use crate::stream::DEFAULT_STREAM_FPS;
+use crate::vec2::Vec2;
+
+pub const MIN_RENDER_SCALE: f32 = 0.1;
+
+/// environment variables and the flag each one stands for, flags given on the command line win
+const ENV_VARS: [(&str, &str); 5] = [
+    ("BOIDS_COUNT", "--count"),
+    ("BOIDS_SEED", "--seed"),
+    ("BOIDS_RADIUS", "--radius"),
+    ("BOIDS_THREADS", "--threads"),
+    ("BOIDS_MAX_NEIGHBORS", "--max-neighbors"),
+];
+
+/// which graphics adapter gets requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterChoice {
+    /// prefers a discrete GPU, falls back to a software adapter if no GPU works with the window
+    HighPerformance,
+    /// prefers an integrated GPU, falls back the same way
+    LowPower,
+    /// only asks for a software adapter, for machines without a usable GPU
+    Fallback,
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub boid_count: usize,
+    pub perception_radius: Option<f32>,
+    /// worker threads used in `State::update`, 1 skips spawning threads at all
+    pub threads: usize,
+    /// below this boid count the update runs sequentially as spawning threads costs more than it saves
+    pub parallel_threshold: usize,
+    pub quadtree_capacity: u16,
+    /// png replacing the embedded boid sprite, also starts in sprite mode
+    pub sprite: Option<PathBuf>,
+    pub max_neighbors: Option<usize>,
+    pub seed: Option<u64>,
+    /// records all input to replay the run later, the control panel is hidden meanwhile
+    pub record_session: Option<PathBuf>,
+    /// replays a recorded session with its world bounds and update rate, live input is ignored meanwhile
+    pub replay: Option<PathBuf>,
+    /// keeps the boids inside this polygon instead of the window
+    pub boundary: Option<Polygon>,
+    /// csv file receiving the timings of every frame
+    pub perf_log: Option<PathBuf>,
+    /// falls back to fifo if the surface doesn't support it
+    pub present_mode: wgpu::PresentMode,
+    pub adapter: AdapterChoice,
+    /// json keyframes driving the parameters over time
+    pub timeline: Option<PathBuf>,
+    /// csv of `x,y,vx,vy` rows used instead of randomly spawned boids, `vx,vy` is the movement per update
+    pub load_boids: Option<PathBuf>,
+    /// exits after rendering this many frames
+    pub max_frames: Option<u64>,
+    pub spawn_speed: SpawnSpeed,
+    pub spawn_pattern: SpawnPattern,
+    /// in radians
+    pub spawn_heading: f32,
+    pub spawn_heading_spread: f32,
+    /// positions kept per boid once trails are shown
+    pub trail_length: usize,
+    /// above this the boids are drawn with fewer vertices, as points if need be
+    pub max_vertices: usize,
+    /// boids are drawn at this fraction of the window resolution and scaled up
+    pub render_scale: f32,
+    /// address the rendered frames are served on, e.g. `0.0.0.0:9000`
+    pub stream: Option<String>,
+    /// the most frames sent per second while streaming
+    pub stream_fps: f32,
+    /// simulation updates per second independent of the framerate, [None] updates once per rendered frame
+    pub update_rate: Option<f32>,
+    /// half extents of the world as `x,y`, kept across resizes instead of following the window
+    pub world_bounds: Option<Vec2>,
+    /// distance to the border at which boids start getting pushed back
+    pub edge_margin: Option<f32>,
+    /// keeps the world square and draws it centered with bars on the sides instead of widening it with the window
+    pub letterbox: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            boid_count: 10000,
+            perception_radius: None,
+            // always 1 on the web, where the simulation can't spawn threads
+            threads: num_cpus::get(),
+            parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            quadtree_capacity: DEFAULT_INDEX_CAPACITY,
+            sprite: None,
+            max_neighbors: None,
+            seed: None,
+            record_session: None,
+            replay: None,
+            boundary: None,
+            perf_log: None,
+            present_mode: wgpu::PresentMode::Fifo,
+            adapter: AdapterChoice::HighPerformance,
+            timeline: None,
+            load_boids: None,
+            max_frames: None,
+            spawn_speed: SpawnSpeed::default(),
+            spawn_pattern: SpawnPattern::Disc,
+            spawn_heading: 0.0,
+            spawn_heading_spread: PI,
+            trail_length: DEFAULT_TRAIL_LENGTH,
+            max_vertices: DEFAULT_MAX_VERTICES,
+            render_scale: 1.0,
+            stream: None,
+            stream_fps: DEFAULT_STREAM_FPS,
+            update_rate: None,
+            world_bounds: None,
+            edge_margin: None,
+            letterbox: false,
+        }
+    }
+}
+
+impl Args {
+    /// command line flags over `BOIDS_*` environment variables over defaults
+    pub fn parse() -> Result<Args, String> {
+        Self::parse_with_env(std::env::vars(), std::env::args().skip(1))
+    }
+
+    pub fn parse_with_env(vars: impl IntoIterator<Item=(String, String)>, args: impl IntoIterator<Item=String>) -> Result<Args, String> {
+        let env_args: Vec<String> = vars.into_iter()
+            .filter_map(|(name, value)| {
+                let (_, flag) = ENV_VARS.iter().find(|(env_name, _)| *env_name == name)?;
+                Some([flag.to_string(), value])
+            })
+            .flatten()
+            .collect();
+
+        let from_env = Args::default().apply(env_args).map_err(|err| format!("{} (set through the environment)", err))?;
+
+        from_env.apply(args)
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item=String>) -> Result<Args, String> {
+        Args::default().apply(args)
+    }
+
+    /// applies the flags in `args` on top of `self`, later flags override earlier ones
+    fn apply(self, args: impl IntoIterator<Item=String>) -> Result<Args, String> {
+        let mut parsed = self;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--count" => parsed.boid_count = parse_value(&arg, args.next())?,
+                "--radius" => {
+                    let radius: f32 = parse_value(&arg, args.next())?;
+
+                    if !(MIN_PERCEPTION_RADIUS..=MAX_PERCEPTION_RADIUS).contains(&radius) {
+                        return Err(format!("'--radius' has to be within {} to {}", MIN_PERCEPTION_RADIUS, MAX_PERCEPTION_RADIUS));
+                    }
+
+                    parsed.perception_radius = Some(radius);
+                }
+                "--threads" => {
+                    parsed.threads = parse_value(&arg, args.next())?;
+
+                    if parsed.threads == 0 {
+                        return Err("'--threads' has to be at least 1".to_string());
+                    }
+                }
+                "--parallel-threshold" => parsed.parallel_threshold = parse_value(&arg, args.next())?,
+                "--quadtree-capacity" => {
+                    parsed.quadtree_capacity = parse_value(&arg, args.next())?;
+
+                    if parsed.quadtree_capacity == 0 {
+                        return Err("'--quadtree-capacity' has to be at least 1".to_string());
+                    }
+                }
+                "--sprite" => parsed.sprite = Some(parse_value(&arg, args.next())?),
+                "--max-neighbors" => parsed.max_neighbors = Some(parse_value(&arg, args.next())?),
+                "--seed" => parsed.seed = Some(parse_value(&arg, args.next())?),
+                "--record-session" => parsed.record_session = Some(parse_value(&arg, args.next())?),
+                "--replay" => parsed.replay = Some(parse_value(&arg, args.next())?),
+                "--spawn-speed" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    parsed.spawn_speed = value.parse().map_err(|err| format!("invalid value for '{}': {}", arg, err))?;
+                }
+                "--spawn-pattern" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    parsed.spawn_pattern = value.parse().map_err(|err| format!("invalid value for '{}': {}", arg, err))?;
+                }
+                "--spawn-heading" => parsed.spawn_heading = parse_value(&arg, args.next())?,
+                "--spawn-heading-spread" => parsed.spawn_heading_spread = parse_value(&arg, args.next())?,
+                "--trail-length" => {
+                    parsed.trail_length = parse_value(&arg, args.next())?;
+
+                    if !(2..=MAX_TRAIL_LENGTH).contains(&parsed.trail_length) {
+                        return Err(format!("'--trail-length' has to be within 2 to {}", MAX_TRAIL_LENGTH));
+                    }
+                }
+                "--max-vertices" => {
+                    parsed.max_vertices = parse_value(&arg, args.next())?;
+
+                    if parsed.max_vertices == 0 {
+                        return Err("'--max-vertices' has to be at least 1".to_string());
+                    }
+                }
+                "--render-scale" => {
+                    parsed.render_scale = parse_value(&arg, args.next())?;
+
+                    if !(MIN_RENDER_SCALE..=1.0).contains(&parsed.render_scale) {
+                        return Err(format!("'--render-scale' has to be within {} to 1", MIN_RENDER_SCALE));
+                    }
+                }
+                "--stream" => parsed.stream = Some(parse_value(&arg, args.next())?),
+                "--stream-fps" => {
+                    parsed.stream_fps = parse_value(&arg, args.next())?;
+
+                    if parsed.stream_fps <= 0.0 {
+                        return Err("'--stream-fps' has to be positive".to_string());
+                    }
+                }
+                "--letterbox" => parsed.letterbox = true,
+                "--world-bounds" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    let bounds = match value.split_once(',').map(|(x, y)| (x.trim().parse::<f32>(), y.trim().parse::<f32>())) {
+                        Some((Ok(x), Ok(y))) if x > 0.0 && y > 0.0 && x.is_finite() && y.is_finite() => Vec2::new(x, y),
+                        _ => return Err(format!("invalid value '{}' for '{}', expected two positive numbers like 2,1", value, arg)),
+                    };
+
+                    parsed.world_bounds = Some(bounds);
+                }
+                "--edge-margin" => {
+                    let margin: f32 = parse_value(&arg, args.next())?;
+
+                    if !(margin >= 0.0 && margin.is_finite()) {
+                        return Err(format!("'{}' must not be negative", arg));
+                    }
+
+                    parsed.edge_margin = Some(margin);
+                }
+                "--update-rate" => {
+                    let rate: f32 = parse_value(&arg, args.next())?;
+
+                    if !(rate > 0.0 && rate.is_finite()) {
+                        return Err(format!("'{}' must be a positive number", arg));
+                    }
+
+                    parsed.update_rate = Some(rate);
+                }
+                "--max-frames" => parsed.max_frames = Some(parse_value(&arg, args.next())?),
+                "--load-boids" => parsed.load_boids = Some(parse_value(&arg, args.next())?),
+                "--timeline" => parsed.timeline = Some(parse_value(&arg, args.next())?),
+                "--perf-log" => parsed.perf_log = Some(parse_value(&arg, args.next())?),
+                "--present-mode" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    parsed.present_mode = match value.as_str() {
+                        "fifo" => wgpu::PresentMode::Fifo,
+                        "mailbox" => wgpu::PresentMode::Mailbox,
+                        "immediate" => wgpu::PresentMode::Immediate,
+                        _ => return Err(format!("invalid value '{}' for '{}', expected fifo, mailbox or immediate", value, arg)),
+                    };
+                }
+                "--adapter" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    parsed.adapter = match value.as_str() {
+                        "high-performance" => AdapterChoice::HighPerformance,
+                        "low-power" => AdapterChoice::LowPower,
+                        "fallback" => AdapterChoice::Fallback,
+                        _ => return Err(format!("invalid value '{}' for '{}', expected high-performance, low-power or fallback", value, arg)),
+                    };
+                }
+                "--boundary" => {
+                    let value = args.next().ok_or_else(|| format!("'{}' expects a value", arg))?;
+
+                    parsed.boundary = Some(value.parse().map_err(|err| format!("invalid value for '{}': {}", arg, err))?);
+                }
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        if parsed.record_session.is_some() && parsed.replay.is_some() {
+            return Err("'--record-session' and '--replay' can't be combined".to_string());
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("'{}' expects a value", name))?;
+
+    value.parse().map_err(|_| format!("invalid value '{}' for '{}'", value, name))
+}
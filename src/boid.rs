@@ -2,76 +2,255 @@ use std::f32::consts::PI;
 
 use rand::Rng;
 
+use crate::boundary::{Boundary, Rectangle};
+use crate::noise::value_noise;
+use crate::shapes::{push_ellipse, push_quad};
+use crate::color::hue;
+use crate::sim_params::{BoundaryMode, JitterMode, SimParams, SpawnPattern};
 use crate::vec2::Vec2;
 use crate::vertex::Vertex;
 
 #[derive(Clone)]
 pub struct Boid {
+    /// inside [SimParams::world_bounds] plus the slack of [SimParams::index_range], boids overshoot the edges before being pushed back.
+    /// this close to the origin f32 resolves steps far smaller than [SimParams::max_speed], f64 isn't needed
     pub location: Vec2,
     pub vel: Vec2,
+    /// size relative to [SIZE], larger boids are heavier
+    pub scale: f32,
+    /// leaders only separate from their neighbors and get followed by everyone else
+    pub is_leader: bool,
+    /// fraction of [SimParams::max_speed] the boid moves at, lost to drag and regained by steering
+    pub speed: f32,
+    /// neighbors found in the last update, only used for coloring
+    pub neighbor_count: u32,
+    /// the steering thread that handled the boid in the last update, only used for coloring
+    pub thread: u32,
+    /// pinned boids never move, their neighbors still see them
+    pub pinned: bool,
+    /// updates since the boid was spawned
+    pub age: u32,
+    /// given at spawn and shown by [ColorMode::Plain](crate::color::ColorMode::Plain)
+    pub color: [f32; 4],
 }
 
-const SIZE: f32 = 0.01 / 4.0;
-const VERTEX_COUNT: u32 = 8;
+pub const SIZE: f32 = 0.01 / 4.0;
+pub const MIN_VERTEX_COUNT: u32 = 3;
+/// half the edge length of a sprite, sprites need to be larger than the plain shape to be recognizable
+pub const SPRITE_SIZE: f32 = SIZE * 4.0;
+/// how much longer than wide a boid gets per unit of distance moved in an update
+const STRETCH_PER_SPEED: f32 = 100.0;
+/// the longest a boid gets relative to its width, reached at 0.015 per update with [STRETCH_PER_SPEED]
+const MAX_STRETCH: f32 = 2.5;
+const SPAWN_RADIUS: f32 = 0.25;
+/// bands of [SpawnPattern::ColorBands], each with its own hue
+const COLOR_BANDS: u32 = 6;
+pub const DEFAULT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+/// range of the random [Boid::scale] given to spawned boids
+const SPAWN_SCALE: (f32, f32) = (0.5, 1.5);
+/// noise features per world unit for [JitterMode::Coherent], small enough that a flock shares one
+const WANDER_SCALE: f32 = 4.0;
+/// how fast the wander noise changes per update
+const WANDER_SPEED: f32 = 0.01;
 
 impl Boid {
-    pub fn new_random() -> Boid {
-        let mut rng = rand::thread_rng();
-
+    /// a plain boid of default size and color at full speed
+    pub fn new(location: Vec2, vel: Vec2) -> Boid {
         Boid {
-            location: Vec2::new(0.0, 0.0),
-            vel: Vec2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0),
+            location,
+            vel,
+            scale: 1.0,
+            is_leader: false,
+            speed: 1.0,
+            neighbor_count: 0,
+            thread: 0,
+            pinned: false,
+            age: 0,
+            color: DEFAULT_COLOR,
         }
     }
 
-    pub fn update(&mut self) {
-        if self.location.x < -0.8 {
-            self.add_vel(&mut Vec2::new(1.0, 0.0), ((-self.location.x - 0.8) / 0.2).powi(3));
-        }
+    pub fn new_random(rng: &mut impl Rng, params: &SimParams) -> Boid {
+        let (location, color) = match params.spawn_pattern {
+            SpawnPattern::Disc => {
+                // spreading the boids out a bit keeps the first frames from being an all to all neighbor search
+                let mut location = Vec2::from_angle(rng.gen::<f32>() * PI * 2.0);
+                location.mul(rng.gen::<f32>().sqrt() * SPAWN_RADIUS);
+
+                (location, DEFAULT_COLOR)
+            }
+            SpawnPattern::ColorBands => {
+                // inside the edge margin, so the bands aren't squeezed right away
+                let half_x = (params.world_bounds.x - params.edge_margin).max(SPAWN_RADIUS);
+                let half_y = (params.world_bounds.y - params.edge_margin).max(SPAWN_RADIUS);
+                let location = Vec2::new(rng.gen_range(-half_x..half_x), rng.gen_range(-half_y..half_y));
 
-        if self.location.x > 0.8 {
-            self.add_vel(&mut Vec2::new(-1.0, 0.0), ((self.location.x - 0.8) / 0.2).powi(3));
+                let band = ((location.y + half_y) / (half_y * 2.0) * COLOR_BANDS as f32).floor().min((COLOR_BANDS - 1) as f32);
+
+                (location, hue(band / COLOR_BANDS as f32))
+            }
+        };
+
+        Boid {
+            location,
+            // uniform over the spread, random components would favor the diagonals
+            vel: Vec2::from_angle(params.spawn_heading + (rng.gen::<f32>() * 2.0 - 1.0) * params.spawn_heading_spread),
+            scale: rng.gen_range(SPAWN_SCALE.0..SPAWN_SCALE.1),
+            is_leader: false,
+            speed: params.spawn_speed.sample(rng),
+            neighbor_count: 0,
+            thread: 0,
+            pinned: false,
+            age: 0,
+            color,
         }
+    }
+
+    /// grows with the area of the boid
+    pub fn mass(&self) -> f32 {
+        self.scale * self.scale
+    }
+
+    /// moves the boid by `dt` updates, steering forces are scaled by it as well
+    ///
+    /// `time` is counted in updates and only drives the coherent jitter, `edge_factor` scales the edge push back
+    pub fn update(&mut self, params: &SimParams, dt: f32, time: f32, edge_factor: f32, rng: &mut impl Rng) {
+        let world = Rectangle::new(params.world_bounds.clone());
+        let boundary: &dyn Boundary = match &params.boundary {
+            Some(boundary) => boundary.as_ref(),
+            None => &world,
+        };
+
+        // pushed back once closer than `edge_margin` to the border, growing with the penetration depth
+        let penetration = boundary.signed_distance(&self.location) + params.edge_margin;
 
-        if self.location.y < -0.8 {
-            self.add_vel(&mut Vec2::new(0.0, 1.0), ((-self.location.y - 0.8) / 0.2).powi(3));
+        if penetration > 0.0 && edge_factor > 0.0 {
+            let mut inwards = boundary.normal(&self.location);
+            inwards.mul(-1.0);
+
+            // without a margin boids are only pushed once outside, dividing by it would make the push infinite
+            let depth = if params.edge_margin > 0.0 { penetration / params.edge_margin } else { 1.0 };
+
+            self.add_vel(&mut inwards, depth.powi(3) * params.edge_force * edge_factor * dt);
         }
 
-        if self.location.y > 0.8 {
-            self.add_vel(&mut Vec2::new(0.0, -1.0), ((self.location.y - 0.8) / 0.2).powi(3));
+        if params.center_pull > 0.0 {
+            let mut to_center = self.location.clone();
+            to_center.mul(-1.0);
+
+            self.add_vel(&mut to_center, params.center_pull * dt);
         }
 
-        self.vel.mul(0.005);
+        self.speed *= (1.0 - params.drag * dt).max(0.0);
+
+        self.vel.mul(params.max_speed * self.speed * dt);
 
         self.location.add(&self.vel);
 
+        if params.boundary_mode == BoundaryMode::Wrap {
+            self.location.x = wrap(self.location.x, params.world_bounds.x);
+            self.location.y = wrap(self.location.y, params.world_bounds.y);
+        }
+
         self.vel.normalize();
 
-        let mut rng = rand::thread_rng();
-        self.add_vel(&mut Vec2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0), 0.2);
+        match params.jitter_mode {
+            JitterMode::White => {
+                self.add_vel(&mut Vec2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0), params.jitter / self.mass() * dt);
+            }
+            JitterMode::Coherent => {
+                let (x, y, z) = (self.location.x * WANDER_SCALE, self.location.y * WANDER_SCALE, time * WANDER_SPEED);
+                // offset so both components come from unrelated parts of the noise
+                let mut wander = Vec2::new(value_noise(x, y, z), value_noise(x + 31.7, y - 17.3, z + 5.1));
+
+                self.add_vel(&mut wander, params.wander_strength / self.mass() * dt);
+            }
+        }
     }
 
+    /// steers towards `vel` and speeds up by the applied force, up to [SimParams::max_speed]
     pub fn add_vel(&mut self, vel: &mut Vec2, factor: f32) {
         vel.mul(factor);
 
+        self.speed = (self.speed + vel.length()).min(1.0);
+
         self.vel.add(vel);
         self.vel.normalize();
     }
 
-    pub fn create_buffer(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, index: u32) {
-        for i in 0..(VERTEX_COUNT) {
-            let angle = ((PI * 2.0) / VERTEX_COUNT as f32) * i as f32;
+    /// blends the heading back towards `previous`, keeping `factor` of it
+    pub fn smooth_heading(&mut self, previous: &Vec2, factor: f32) {
+        self.vel.mul(1.0 - factor);
 
-            vertices.push(Vertex {
-                position: [self.location.x + angle.cos() * SIZE, self.location.y + angle.sin() * SIZE, 0.0],
-                color: [1.0, 1.0, 1.0],
-            });
-        }
+        let mut previous = previous.clone();
+        previous.mul(factor);
+
+        self.vel.add(&previous);
+        self.vel.normalize();
+    }
+
+    /// appends the boid as a triangle fan with `vertex_count` outer vertices (at least [MIN_VERTEX_COUNT])
+    ///
+    /// it's stretched along its heading the faster it moves with `max_speed`, resting boids are round.
+    /// the shape is in world units, the [Camera](crate::camera::Camera) keeps it from being stretched by the window
+    pub fn create_buffer(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, vertex_count: u32, max_speed: f32, color: [f32; 4]) {
+        let radius = SIZE * self.scale;
+        let stretch = (1.0 + self.speed * max_speed * STRETCH_PER_SPEED).clamp(1.0, MAX_STRETCH);
+
+        push_ellipse(vertices, indices, &self.location, (radius * stretch, radius), self.vel.angle(), vertex_count.max(MIN_VERTEX_COUNT), color);
+    }
+
+    /// appends the boid as a single quad, the circle itself is cut out by `fs_circle`
+    pub fn create_circle_buffer(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, color: [f32; 4]) {
+        let radius = SIZE * self.scale;
+        push_quad(vertices, indices, &self.location, (radius, radius), 0.0, color);
+    }
+
+    /// appends the boid as a textured quad facing its velocity
+    pub fn create_sprite_buffer(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, color: [f32; 4]) {
+        push_quad(vertices, indices, &self.location, (SPRITE_SIZE * self.scale, SPRITE_SIZE * self.scale), self.vel.angle(), color);
+    }
+
+    /// appends the boid as a single vertex for point list rendering
+    pub fn create_point_buffer(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, color: [f32; 4]) {
+        indices.push(vertices.len() as u32);
+
+        vertices.push(Vertex {
+            position: [self.location.x, self.location.y, 0.0],
+            color,
+            uv: [0.0, 0.0],
+        });
+    }
+}
+
+/// moves `value` into `-half_extent..half_extent`, coming back in on the opposite side
+fn wrap(value: f32, half_extent: f32) -> f32 {
+    (value + half_extent).rem_euclid(half_extent * 2.0) - half_extent
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    use super::*;
 
-        for i in 0..(VERTEX_COUNT - 2) {
-            indices.push(index * VERTEX_COUNT);
-            indices.push(index * VERTEX_COUNT + i + 1);
-            indices.push(index * VERTEX_COUNT + i + 2);
+    #[test]
+    fn drag_slows_down_boids_without_forces() {
+        let params = SimParams { drag: 0.05, jitter: 0.0, ..SimParams::default() };
+        let mut rng = ChaCha12Rng::seed_from_u64(42);
+
+        let mut boid = Boid::new_random(&mut rng, &params);
+        boid.location = Vec2::ZERO;
+        boid.speed = 1.0;
+
+        for _ in 0..200 {
+            let previous_speed = boid.speed;
+            boid.update(&params, 1.0, 0.0, 1.0, &mut rng);
+
+            assert!(boid.speed < previous_speed, "{} after {}", boid.speed, previous_speed);
         }
+
+        assert!(boid.speed < 0.001, "still at {}", boid.speed);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::boid::Boid;
+use crate::vec2::Vec2;
+
+const HEADER: &str = "x,y,vx,vy";
+
+/// reads boids from `x,y,vx,vy` rows, an optional header and empty lines are skipped
+///
+/// every boid has to lie within `world_bounds`, errors name the offending line.
+/// `vx,vy` is the distance moved per update, longer than `max_speed` is cut down to it
+pub fn load(path: &Path, world_bounds: &Vec2, max_speed: f32) -> io::Result<Vec<Boid>> {
+    let reader = BufReader::new(File::open(path)?);
+    let invalid = |line_number: usize, message: String| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_number, message));
+
+    let mut boids = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        let line_number = index + 1;
+
+        if line.is_empty() || (index == 0 && line == HEADER) {
+            continue;
+        }
+
+        let values = line.split(',')
+            .map(|value| value.trim().parse::<f32>().map_err(|err| invalid(line_number, format!("'{}' is not a number: {}", value.trim(), err))))
+            .collect::<io::Result<Vec<f32>>>()?;
+
+        let [x, y, vx, vy] = values[..] else {
+            return Err(invalid(line_number, format!("expected 4 values ({}), got {}", HEADER, values.len())));
+        };
+
+        // NaN compares false against the bounds and would get through
+        if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+            return Err(invalid(line_number, format!("{} is not a finite number", value)));
+        }
+
+        if x.abs() > world_bounds.x || y.abs() > world_bounds.y {
+            return Err(invalid(line_number, format!("{} is outside of the world {}", Vec2::new(x, y), world_bounds)));
+        }
+
+        let mut vel = Vec2::new(vx, vy);
+        let speed = if max_speed > 0.0 { (vel.length() / max_speed).min(1.0) } else { 0.0 };
+        vel.normalize();
+
+        boids.push(Boid { speed, ..Boid::new(Vec2::new(x, y), vel) });
+    }
+
+    Ok(boids)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn load_str(name: &str, content: &str) -> io::Result<Vec<Boid>> {
+        let path = std::env::temp_dir().join(format!("boids_csv_{}_{}.csv", name, std::process::id()));
+        fs::write(&path, content)?;
+
+        let boids = load(&path, &Vec2::new(1.0, 1.0), 0.005);
+        fs::remove_file(&path)?;
+
+        boids
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        let Err(err) = load_str("nan", "x,y,vx,vy\n0.1,0.1,0.001,0\nNaN,0.2,0.001,0\n") else {
+            panic!("NaN was accepted");
+        };
+
+        assert!(err.to_string().starts_with("line 3:"), "{}", err);
+    }
+
+    #[test]
+    fn speed_follows_the_velocity_length() {
+        let boids = load_str("speed", "0,0,0.0025,0\n0,0,0,-0.05\n").unwrap();
+
+        assert!((boids[0].speed - 0.5).abs() < 1e-6);
+        assert_eq!(boids[1].speed, 1.0);
+        assert!(boids[1].vel.approx_eq(&Vec2::new(0.0, -1.0), 1e-6));
+    }
+}
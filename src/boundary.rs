@@ -0,0 +1,115 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::vec2::Vec2;
+
+/// the area boids are kept in
+pub trait Boundary: fmt::Debug + Send + Sync {
+    /// distance of `point` to the border, negative inside
+    fn signed_distance(&self, point: &Vec2) -> f32;
+
+    /// the border as a closed loop of points, used for rendering
+    fn outline(&self) -> Vec<Vec2>;
+
+    /// direction in which [Boundary::signed_distance] grows the fastest
+    fn normal(&self, point: &Vec2) -> Vec2 {
+        const EPSILON: f32 = 1e-4;
+
+        let mut normal = Vec2::new(
+            self.signed_distance(&Vec2::new(point.x + EPSILON, point.y)) - self.signed_distance(&Vec2::new(point.x - EPSILON, point.y)),
+            self.signed_distance(&Vec2::new(point.x, point.y + EPSILON)) - self.signed_distance(&Vec2::new(point.x, point.y - EPSILON)),
+        );
+        normal.normalize();
+
+        normal
+    }
+}
+
+/// an axis aligned rectangle centered at the origin
+#[derive(Debug, Clone)]
+pub struct Rectangle {
+    pub half_extents: Vec2,
+}
+
+impl Rectangle {
+    pub fn new(half_extents: Vec2) -> Rectangle {
+        Rectangle { half_extents }
+    }
+}
+
+impl Boundary for Rectangle {
+    fn signed_distance(&self, point: &Vec2) -> f32 {
+        let x = point.x.abs() - self.half_extents.x;
+        let y = point.y.abs() - self.half_extents.y;
+
+        let outside = Vec2::new(x.max(0.0), y.max(0.0)).length();
+        let inside = x.max(y).min(0.0);
+
+        outside + inside
+    }
+
+    fn outline(&self) -> Vec<Vec2> {
+        let Vec2 { x, y } = self.half_extents;
+
+        vec![Vec2::new(-x, -y), Vec2::new(x, -y), Vec2::new(x, y), Vec2::new(-x, y)]
+    }
+}
+
+/// a simple polygon, the points may be given in either winding order
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+impl Boundary for Polygon {
+    fn signed_distance(&self, point: &Vec2) -> f32 {
+        let mut distance = f32::MAX;
+        let mut inside = false;
+
+        let mut previous = &self.points[self.points.len() - 1];
+
+        for current in &self.points {
+            let edge = Vec2::new(previous.x - current.x, previous.y - current.y);
+            let to_point = Vec2::new(point.x - current.x, point.y - current.y);
+
+            let t = ((to_point.x * edge.x + to_point.y * edge.y) / (edge.x * edge.x + edge.y * edge.y)).clamp(0.0, 1.0);
+            distance = distance.min(Vec2::new(to_point.x - edge.x * t, to_point.y - edge.y * t).length());
+
+            // even odd rule, counts the edges crossed by a ray towards +x
+            if (current.y > point.y) != (previous.y > point.y) && point.x < current.x + edge.x * (point.y - current.y) / edge.y {
+                inside = !inside;
+            }
+
+            previous = current;
+        }
+
+        if inside { -distance } else { distance }
+    }
+
+    fn outline(&self) -> Vec<Vec2> {
+        self.points.clone()
+    }
+}
+
+/// parses space separated `x,y` points, at least 3 are needed
+impl FromStr for Polygon {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let points = s.split_whitespace()
+            .map(|point| {
+                let (x, y) = point.split_once(',').ok_or_else(|| format!("'{}' is not a 'x,y' point", point))?;
+                let x = x.parse().map_err(|_| format!("invalid coordinate '{}'", x))?;
+                let y = y.parse().map_err(|_| format!("invalid coordinate '{}'", y))?;
+
+                Ok(Vec2::new(x, y))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if points.len() < 3 {
+            return Err("a polygon needs at least 3 points".to_string());
+        }
+
+        Ok(Polygon { points })
+    }
+}
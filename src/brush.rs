@@ -0,0 +1,59 @@
+use std::f32::consts::PI;
+
+use crate::vec2::Vec2;
+
+/// which way a [Brush] pushes the boids under it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushMode {
+    Attract,
+    Repel,
+    /// turns them around the center, counterclockwise
+    Swirl,
+}
+
+/// a force painted into a circle, applied once per update while it's held
+#[derive(Debug, Clone)]
+pub struct Brush {
+    pub center: Vec2,
+    pub radius: f32,
+    /// the push right at the center, it falls off linearly to 0 at the edge
+    pub strength: f32,
+    pub mode: BrushMode,
+}
+
+impl Brush {
+    /// the push on a boid at `location`, [None] outside the brush
+    pub fn force(&self, location: &Vec2) -> Option<Vec2> {
+        let mut offset = location.clone();
+        offset.sub(&self.center);
+
+        let distance = offset.length();
+
+        if distance >= self.radius {
+            return None;
+        }
+
+        offset.normalize();
+
+        let mut force = match self.mode {
+            BrushMode::Attract => Vec2::new(-offset.x, -offset.y),
+            BrushMode::Repel => offset,
+            BrushMode::Swirl => Vec2::new(-offset.y, offset.x),
+        };
+
+        force.mul(self.strength * (1.0 - distance / self.radius));
+        Some(force)
+    }
+
+    /// the border as a closed loop of points, used for rendering
+    pub fn outline(&self, segments: u32) -> Vec<Vec2> {
+        (0..segments)
+            .map(|segment| {
+                let mut point = Vec2::from_angle(segment as f32 / segments as f32 * PI * 2.0);
+                point.mul(self.radius);
+                point.add(&self.center);
+                point
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,271 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::vec2::Vec2;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct CameraUniform {
+    scale: [f32; 2],
+    center: [f32; 2],
+}
+
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 64.0;
+
+/// maps world coordinates to clip space
+///
+/// the world is as wide as the window aspect ratio requires and always spans `-1..1` vertically,
+/// so distances are the same along both axes. unzoomed the view shows all of the world
+///
+/// letterboxed the world stays square instead and is drawn into the centered square of the window, see [Camera::viewport].
+/// a world of fixed size is fit into the window at any aspect ratio, with the rest of the window left empty
+pub struct Camera {
+    world_bounds: Vec2,
+    /// the world set by the user, kept across resizes, [None] follows the window
+    fixed_world_bounds: Option<Vec2>,
+    /// half extents of the view unzoomed, the world plus whatever the window shows around it
+    view_bounds: Vec2,
+    letterbox: bool,
+    /// the world position in the middle of the window
+    center: Vec2,
+    /// 2 shows half of the world along each axis
+    zoom: f32,
+    buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Camera {
+    pub fn new(device: &wgpu::Device, size: winit::dpi::PhysicalSize<u32>, letterbox: bool, fixed_world_bounds: Option<Vec2>) -> Camera {
+        let world_bounds = fixed_world_bounds.clone().unwrap_or_else(|| Self::world_bounds_for(size, letterbox));
+        let view_bounds = Self::view_bounds_for(&world_bounds, size, letterbox);
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[Self::uniform(&view_bounds, &Vec2::ZERO, 1.0)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Camera {
+            world_bounds,
+            fixed_world_bounds,
+            view_bounds,
+            letterbox,
+            center: Vec2::ZERO,
+            zoom: 1.0,
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /// the half extents of the world visible in a window of `size`
+    ///
+    /// the world never grows with the window, a larger window just shows it at a higher resolution.
+    /// that keeps boid positions small enough for f32
+    pub fn world_bounds_for(size: winit::dpi::PhysicalSize<u32>, letterbox: bool) -> Vec2 {
+        if letterbox {
+            return Vec2::new(1.0, 1.0);
+        }
+
+        Vec2::new(size.width as f32 / size.height.max(1) as f32, 1.0)
+    }
+
+    /// the half extents of the view that fits all of `world_bounds` into a window of `size` without stretching it
+    ///
+    /// the same as the world when it follows the window
+    pub fn view_bounds_for(world_bounds: &Vec2, size: winit::dpi::PhysicalSize<u32>, letterbox: bool) -> Vec2 {
+        let aspect = if letterbox { 1.0 } else { size.width.max(1) as f32 / size.height.max(1) as f32 };
+
+        if world_bounds.x / world_bounds.y > aspect {
+            Vec2::new(world_bounds.x, world_bounds.x / aspect)
+        } else {
+            Vec2::new(world_bounds.y * aspect, world_bounds.y)
+        }
+    }
+
+    /// the part of a target of `size` the world is drawn into, as x, y, width and height in pixels from the top left
+    ///
+    /// the whole target, or the largest centered square of it when letterboxed
+    pub fn viewport(&self, size: winit::dpi::PhysicalSize<u32>) -> [f32; 4] {
+        let (width, height) = (size.width as f32, size.height as f32);
+
+        if !self.letterbox {
+            return [0.0, 0.0, width, height];
+        }
+
+        let side = width.min(height);
+        [((width - side) / 2.0).floor(), ((height - side) / 2.0).floor(), side, side]
+    }
+
+    pub fn world_bounds(&self) -> &Vec2 {
+        &self.world_bounds
+    }
+
+    /// the world bounds set by the user, [None] while the world follows the window
+    pub fn fixed_world_bounds(&self) -> Option<&Vec2> {
+        self.fixed_world_bounds.as_ref()
+    }
+
+    pub fn resize(&mut self, queue: &wgpu::Queue, size: winit::dpi::PhysicalSize<u32>) {
+        self.world_bounds = self.fixed_world_bounds.clone().unwrap_or_else(|| Self::world_bounds_for(size, self.letterbox));
+        self.view_bounds = Self::view_bounds_for(&self.world_bounds, size, self.letterbox);
+
+        self.write(queue);
+    }
+
+    /// keeps the world at `world_bounds` from now on, [None] makes it follow the window again
+    pub fn set_world_bounds(&mut self, queue: &wgpu::Queue, size: winit::dpi::PhysicalSize<u32>, world_bounds: Option<Vec2>) {
+        self.fixed_world_bounds = world_bounds;
+
+        self.resize(queue, size);
+    }
+
+    pub fn center(&self) -> &Vec2 {
+        &self.center
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// moves the view so `center` is in the middle of the window
+    pub fn set_center(&mut self, queue: &wgpu::Queue, center: Vec2) {
+        self.center = center;
+
+        self.write(queue);
+    }
+
+    /// zooms by `factor` while keeping the world position `anchor` at the same spot in the window
+    pub fn zoom_around(&mut self, queue: &wgpu::Queue, factor: f32, anchor: &Vec2) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let ratio = self.zoom / zoom;
+
+        self.center = Vec2::new(
+            anchor.x + (self.center.x - anchor.x) * ratio,
+            anchor.y + (self.center.y - anchor.y) * ratio,
+        );
+        self.zoom = zoom;
+
+        self.write(queue);
+    }
+
+    /// centers the view on the box from `min` to `max` and zooms so all of it is visible
+    pub fn fit(&mut self, queue: &wgpu::Queue, min: &Vec2, max: &Vec2) {
+        let half_x = ((max.x - min.x) / 2.0).max(f32::EPSILON);
+        let half_y = ((max.y - min.y) / 2.0).max(f32::EPSILON);
+
+        self.center = Vec2::new((min.x + max.x) / 2.0, (min.y + max.y) / 2.0);
+        self.zoom = (self.view_bounds.x / half_x).min(self.view_bounds.y / half_y).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        self.write(queue);
+    }
+
+    /// goes back to showing all of the world
+    pub fn reset_view(&mut self, queue: &wgpu::Queue) {
+        self.center = Vec2::ZERO;
+        self.zoom = 1.0;
+
+        self.write(queue);
+    }
+
+    /// the lower left and upper right corner of the visible part of the world
+    pub fn visible_bounds(&self) -> (Vec2, Vec2) {
+        let half_x = self.view_bounds.x / self.zoom;
+        let half_y = self.view_bounds.y / self.zoom;
+
+        (
+            Vec2::new(self.center.x - half_x, self.center.y - half_y),
+            Vec2::new(self.center.x + half_x, self.center.y + half_y),
+        )
+    }
+
+    /// converts a position in window pixels to world coordinates, positions in the letterbox bars end up outside the world
+    pub fn screen_to_world(&self, position: (f64, f64), size: winit::dpi::PhysicalSize<u32>) -> Vec2 {
+        let [x, y, width, height] = self.viewport(size).map(|value| value as f64);
+
+        Vec2::new(
+            self.center.x + ((position.0 - x) / width * 2.0 - 1.0) as f32 * self.view_bounds.x / self.zoom,
+            self.center.y + (1.0 - (position.1 - y) / height * 2.0) as f32 * self.view_bounds.y / self.zoom,
+        )
+    }
+
+    /// how many pixels one world unit covers in a window of `size`
+    pub fn pixels_per_unit(&self, size: winit::dpi::PhysicalSize<u32>) -> f32 {
+        self.viewport(size)[3] * self.zoom / (self.view_bounds.y * 2.0)
+    }
+
+    fn write(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[Self::uniform(&self.view_bounds, &self.center, self.zoom)]));
+    }
+
+    /// scales world units by `zoom / view_bounds` on each axis, which covers the same pixels on both as the view has the window's aspect ratio
+    fn uniform(view_bounds: &Vec2, center: &Vec2, zoom: f32) -> CameraUniform {
+        CameraUniform {
+            scale: [zoom / view_bounds.x, zoom / view_bounds.y],
+            center: [center.x, center.y],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+
+    #[test]
+    fn world_units_cover_the_same_pixels_on_both_axes() {
+        for size in [PhysicalSize::new(3000, 200), PhysicalSize::new(200, 3000), PhysicalSize::new(800, 600)] {
+            for (letterbox, fixed_world_bounds) in [(false, None), (true, None), (false, Some(Vec2::new(4.0, 1.5))), (true, Some(Vec2::new(0.5, 3.0)))] {
+                let world_bounds = fixed_world_bounds.clone().unwrap_or_else(|| Camera::world_bounds_for(size, letterbox));
+                let view_bounds = Camera::view_bounds_for(&world_bounds, size, letterbox);
+                let uniform = Camera::uniform(&view_bounds, &Vec2::ZERO, 2.0);
+
+                // all of the world is in view
+                assert!(view_bounds.x >= world_bounds.x && view_bounds.y >= world_bounds.y, "{} can't show {}", view_bounds, world_bounds);
+
+                // clip space spans 2 across the viewport on both axes
+                let (width, height) = if letterbox {
+                    let side = size.width.min(size.height) as f32;
+                    (side, side)
+                } else {
+                    (size.width as f32, size.height as f32)
+                };
+
+                let pixels_x = uniform.scale[0] * width / 2.0;
+                let pixels_y = uniform.scale[1] * height / 2.0;
+
+                assert!((pixels_x - pixels_y).abs() < 1e-3, "{:?} letterboxed {} world {}: {} and {} pixels", size, letterbox, world_bounds, pixels_x, pixels_y);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::f32::consts::PI;
+
+use crate::boid::Boid;
+
+/// neighbor count at which a boid is shown as fully crowded
+const CROWDED_NEIGHBORS: u32 = 32;
+/// the fractional part of the golden ratio
+const GOLDEN_RATIO: f32 = 0.618_034;
+
+/// how boids are colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// the color each boid got at spawn
+    Plain,
+    /// hue follows the direction of flight
+    Heading,
+    /// blue for isolated boids up to red for crowded ones
+    Neighbors,
+    /// a distinct hue per steering thread, shows how the boids were split between them
+    Threads,
+}
+
+impl ColorMode {
+    pub fn next(self) -> ColorMode {
+        match self {
+            ColorMode::Plain => ColorMode::Heading,
+            ColorMode::Heading => ColorMode::Neighbors,
+            ColorMode::Neighbors => ColorMode::Threads,
+            ColorMode::Threads => ColorMode::Plain,
+        }
+    }
+
+    pub fn color(self, boid: &Boid) -> [f32; 4] {
+        match self {
+            ColorMode::Plain => boid.color,
+            ColorMode::Heading => self.gradient((boid.vel.angle() + PI) / (PI * 2.0)),
+            ColorMode::Neighbors => self.gradient(boid.neighbor_count.min(CROWDED_NEIGHBORS) as f32 / CROWDED_NEIGHBORS as f32),
+            // steps by the golden ratio keep neighboring threads apart without knowing how many there are
+            ColorMode::Threads => hue(boid.thread as f32 * GOLDEN_RATIO),
+        }
+    }
+
+    /// the color at `t` in `0..=1` along the legend of this mode
+    pub fn gradient(self, t: f32) -> [f32; 4] {
+        match self {
+            ColorMode::Plain | ColorMode::Threads => [1.0, 1.0, 1.0, 1.0],
+            ColorMode::Heading => hue(t),
+            ColorMode::Neighbors => [t, 0.0, 1.0 - t, 1.0],
+        }
+    }
+
+    /// the labels for both ends of the legend, [None] if the mode doesn't need one
+    pub fn legend_labels(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ColorMode::Plain | ColorMode::Threads => None,
+            ColorMode::Heading => Some(("-180°", "180°")),
+            ColorMode::Neighbors => Some(("0", "32+")),
+        }
+    }
+}
+
+/// a fully saturated color, `t` in `0..=1` goes around the color wheel once starting at red
+pub fn hue(t: f32) -> [f32; 4] {
+    let h = t.rem_euclid(1.0) * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    [r, g, b, 1.0]
+}
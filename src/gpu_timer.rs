@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// size of one resolved timestamp
+const TIMESTAMP_SIZE: wgpu::BufferAddress = std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+/// measures the GPU time between [GpuTimer::begin] and [GpuTimer::end] with timestamp queries
+///
+/// the results are read back without waiting on the GPU, so they arrive a few frames late
+/// and frames are skipped while the last result hasn't been read yet
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    readback_buffer: wgpu::Buffer,
+    /// nanoseconds per timestamp tick
+    period: f32,
+    /// a resolve into the readback buffer was submitted and not read yet
+    pending: bool,
+    /// the resolve was only recorded, the buffer gets mapped once it's submitted
+    needs_map: bool,
+    /// set by the map callback
+    mapped: Arc<AtomicBool>,
+}
+
+impl GpuTimer {
+    /// [None] if `device` was created without [wgpu::Features::TIMESTAMP_QUERY]
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<GpuTimer> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU Timer Queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+
+        // the queries are resolved straight into the buffer that gets mapped
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timer Readback Buffer"),
+            size: 2 * TIMESTAMP_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(GpuTimer {
+            query_set,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn begin(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.write_timestamp(&self.query_set, 0);
+    }
+
+    /// also resolves both timestamps for reading back, unless the last ones are still pending
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.write_timestamp(&self.query_set, 1);
+
+        if self.pending {
+            return;
+        }
+
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.readback_buffer, 0);
+
+        self.pending = true;
+        self.needs_map = true;
+    }
+
+    /// called after the encoder passed to [GpuTimer::end] was submitted
+    pub fn submitted(&mut self) {
+        if !self.needs_map {
+            return;
+        }
+
+        self.needs_map = false;
+
+        let mapped = self.mapped.clone();
+        self.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            match result {
+                Ok(()) => mapped.store(true, Ordering::Release),
+                Err(err) => log::warn!("couldn't read back GPU timestamps: {}", err),
+            }
+        });
+    }
+
+    /// the measured time in milliseconds, once the GPU finished a submitted measurement
+    pub fn read(&mut self, device: &wgpu::Device) -> Option<f64> {
+        if !self.pending || self.needs_map {
+            return None;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return None;
+        }
+
+        let slice = self.readback_buffer.slice(..);
+        let timestamps: Vec<u64> = slice.get_mapped_range()
+            .chunks_exact(TIMESTAMP_SIZE as usize)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+
+        self.readback_buffer.unmap();
+        self.pending = false;
+
+        let ticks = timestamps[1].saturating_sub(timestamps[0]);
+        Some(ticks as f64 * self.period as f64 / 1_000_000.0)
+    }
+}
@@ -0,0 +1,92 @@
+use winit::event::WindowEvent;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::Window;
+
+/// immediate mode control panel drawn on top of the simulation
+pub struct Gui {
+    ctx: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    pub visible: bool,
+}
+
+impl Gui {
+    pub fn new<T>(event_loop: &EventLoopWindowTarget<T>, window: &Window, device: &wgpu::Device, format: wgpu::TextureFormat) -> Gui {
+        let mut state = egui_winit::State::new(event_loop);
+        state.set_pixels_per_point(window.scale_factor() as f32);
+
+        Gui {
+            ctx: egui::Context::default(),
+            state,
+            renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+            visible: true,
+        }
+    }
+
+    /// returns true if egui wants exclusive use of the event
+    pub fn on_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        self.state.on_event(&self.ctx, event).consumed
+    }
+
+    /// runs `ui` and records drawing it into `encoder`
+    ///
+    /// the returned command buffers have to be submitted before `encoder`
+    pub fn render(
+        &mut self,
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        ui: impl FnOnce(&egui::Context),
+    ) -> Vec<wgpu::CommandBuffer> {
+        if !self.visible {
+            return Vec::new();
+        }
+
+        let raw_input = self.state.take_egui_input(window);
+        let full_output = self.ctx.run(raw_input, ui);
+
+        self.state.handle_platform_output(window, &self.ctx, full_output.platform_output);
+
+        let paint_jobs = self.ctx.tessellate(full_output.shapes);
+        let size = window.inner_size();
+        let screen_descriptor = egui_wgpu::renderer::ScreenDescriptor {
+            size_in_pixels: [size.width, size.height],
+            pixels_per_point: self.state.pixels_per_point(),
+        };
+
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.renderer.update_texture(device, queue, *id, image_delta);
+        }
+
+        let command_buffers = self.renderer.update_buffers(device, queue, encoder, &paint_jobs, &screen_descriptor);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Gui Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            self.renderer.render(&mut render_pass, &paint_jobs, &screen_descriptor);
+        }
+
+        for id in &full_output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+
+        command_buffers
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+/// the part of a [WindowEvent] the simulation reacts to, small enough to be recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum InputEvent {
+    KeyPressed(VirtualKeyCode),
+    /// scrolled lines, positive is away from the user
+    MouseWheel(f32),
+    CursorMoved(f64, f64),
+    CursorLeft,
+    MousePressed(MouseButton),
+    MouseReleased(MouseButton),
+    ModifiersChanged(ModifiersState),
+    /// the world bounds after the window was resized, never created from a [WindowEvent]
+    WorldResized(f32, f32),
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<InputEvent> {
+        match event {
+            WindowEvent::KeyboardInput {
+                input:
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => Some(InputEvent::KeyPressed(*key)),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                };
+
+                Some(InputEvent::MouseWheel(lines))
+            }
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved(position.x, position.y)),
+            WindowEvent::CursorLeft { .. } => Some(InputEvent::CursorLeft),
+            WindowEvent::MouseInput { state: ElementState::Pressed, button, .. } => Some(InputEvent::MousePressed(*button)),
+            WindowEvent::MouseInput { state: ElementState::Released, button, .. } => Some(InputEvent::MouseReleased(*button)),
+            WindowEvent::ModifiersChanged(modifiers) => Some(InputEvent::ModifiersChanged(*modifiers)),
+            _ => None
+        }
+    }
+}
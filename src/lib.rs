@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use instant::Instant;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::WindowBuilder;
+
+use crate::args::Args;
+use crate::state::State;
+
+pub mod args;
+pub mod gpu_timer;
+pub mod gui;
+pub mod input;
+pub mod vertex;
+pub mod state;
+pub mod boid;
+pub mod boid_csv;
+pub mod boundary;
+pub mod brush;
+pub mod camera;
+pub mod color;
+pub mod vec2;
+pub mod session;
+pub mod shapes;
+pub mod mesh;
+pub mod noise;
+pub mod perf_log;
+pub mod pipeline;
+pub mod readback;
+pub mod sim_params;
+pub mod simulation;
+pub mod snapshot;
+pub mod spatial;
+pub mod stats;
+pub mod steering;
+pub mod stream;
+pub mod texture;
+pub mod timeline;
+pub mod trails;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
+pub async fn run() {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+            console_log::init_with_level(log::Level::Warn).expect("Couldn't initialize logger");
+        } else {
+            env_logger::init();
+        }
+    }
+
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(err) => fail(2, err),
+    };
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        // Winit prevents sizing with CSS, so we have to set
+        // the size manually when on web.
+        use winit::dpi::PhysicalSize;
+        window.set_inner_size(PhysicalSize::new(800, 600));
+
+        use winit::platform::web::WindowExtWebSys;
+        web_sys::window()
+            .and_then(|win| win.document())
+            .and_then(|doc| {
+                let dst = doc.get_element_by_id("boids")?;
+                let canvas = web_sys::Element::from(window.canvas());
+                dst.append_child(&canvas).ok()?;
+                Some(())
+            })
+            .expect("Couldn't append canvas to document body.");
+    }
+
+    let mut state = match State::new(&window, &event_loop, &args).await {
+        Ok(state) => state,
+        Err(err) => fail(1, format!("failed to start boids: {}", err)),
+    };
+
+    // updating the title every frame would hammer the windowing system
+    const TITLE_INTERVAL: Duration = Duration::from_millis(500);
+    let mut title_time = Instant::now();
+    let mut title_frames = 0;
+
+    event_loop.run(move |event, _, control_flow|
+        match event {
+            Event::RedrawRequested(window_id) => {
+                if window_id != window.id() {
+                    return;
+                }
+
+                state.update();
+                match state.render(&window) {
+                    Ok(_) => {}
+                    // Reconfigure the surface if lost
+                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                    // The system is out of memory, we should probably quit
+                    Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
+                    // Outdated should be resolved by the next frame, timeouts already drop the frame in render
+                    Err(e) => eprintln!("{:?}", e),
+                }
+
+                if let Some(err) = state.device_error() {
+                    // recreating the device isn't supported, exiting still saves the recording and prints the stats
+                    eprintln!("the graphics device failed, it may have been lost to a driver reset or GPU switch: {}", err);
+                    *control_flow = ControlFlow::Exit;
+                } else if state.frame_limit_reached() {
+                    *control_flow = ControlFlow::Exit;
+                }
+
+                title_frames += 1;
+
+                let elapsed = title_time.elapsed();
+                if elapsed >= TITLE_INTERVAL {
+                    let fps = title_frames as f64 / elapsed.as_secs_f64();
+                    window.set_title(&format!("boids - {} boids - {:.1} fps", state.boid_count(), fps));
+
+                    title_time = Instant::now();
+                    title_frames = 0;
+                }
+            }
+            Event::LoopDestroyed => {
+                if let Err(err) = state.save_recording() {
+                    eprintln!("failed to save the recorded session: {}", err);
+                }
+
+                if let Err(err) = state.flush_perf_log() {
+                    eprintln!("failed to write the performance log: {}", err);
+                }
+
+                println!("{}", state.stats);
+            }
+            Event::MainEventsCleared => {
+                // RedrawRequested will only trigger once, unless we manually
+                // request it.
+                window.request_redraw();
+            }
+            Event::WindowEvent { ref event, window_id } => {
+                if window_id != window.id() || state.input(event) {
+                    return;
+                }
+
+                match event {
+                    WindowEvent::CloseRequested | WindowEvent::KeyboardInput {
+                        input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            ..
+                        },
+                        ..
+                    } => *control_flow = ControlFlow::Exit,
+                    // redrawing right away keeps some platforms from stretching the last frame until the next one
+                    WindowEvent::Resized(physical_size) => {
+                        state.resize(*physical_size);
+                        window.request_redraw();
+                    }
+                    WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
+                        state.set_scale_factor(*scale_factor);
+                        state.resize(**new_inner_size);
+                        window.request_redraw();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        });
+}
+
+
+/// reports an error that makes running impossible and stops
+fn fail(code: i32, message: String) -> ! {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            // there is no process to exit, the panic hook shows the message in the console
+            let _ = code;
+            panic!("{}", message);
+        } else {
+            eprintln!("{}", message);
+            std::process::exit(code);
+        }
+    }
+}
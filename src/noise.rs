@@ -0,0 +1,32 @@
+/// smoothly varying noise in `-1..=1` with features about one unit apart
+///
+/// random values on an integer lattice, blended with smoothstep in between
+pub fn value_noise(x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (smoothstep(x - x0), smoothstep(y - y0), smoothstep(z - z0));
+    let (x0, y0, z0) = (x0 as i32, y0 as i32, z0 as i32);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let corner = |dx: i32, dy: i32, dz: i32| lattice_value(x0 + dx, y0 + dy, z0 + dz);
+
+    let near = lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), tx), lerp(corner(0, 1, 0), corner(1, 1, 0), tx), ty);
+    let far = lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), tx), lerp(corner(0, 1, 1), corner(1, 1, 1), tx), ty);
+
+    lerp(near, far, tz)
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// a fixed pseudo random value in `-1..=1` for every lattice point
+fn lattice_value(x: i32, y: i32, z: i32) -> f32 {
+    let mut hash = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841) ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297a_2d39);
+    hash ^= hash >> 15;
+
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
@@ -0,0 +1,38 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// per frame timings in milliseconds written as csv
+pub struct PerfLog {
+    writer: BufWriter<File>,
+}
+
+/// the timings of a single frame, the update ones are [None] when no update ran, e.g. while paused
+pub struct FrameTimes {
+    pub frame: u64,
+    pub index: Option<f64>,
+    pub steer: Option<f64>,
+    pub write_back: Option<f64>,
+    pub render: f64,
+}
+
+impl PerfLog {
+    pub fn create(path: &Path) -> io::Result<PerfLog> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writeln!(writer, "frame,index_ms,steer_ms,write_back_ms,render_ms")?;
+
+        Ok(PerfLog { writer })
+    }
+
+    pub fn write(&mut self, times: &FrameTimes) -> io::Result<()> {
+        let optional = |time: Option<f64>| time.map(|time| format!("{:.4}", time)).unwrap_or_default();
+
+        writeln!(self.writer, "{},{},{},{},{:.4}", times.frame, optional(times.index), optional(times.steer), optional(times.write_back), times.render)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
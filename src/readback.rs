@@ -0,0 +1,231 @@
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// RGBA pixels of a rendered frame, rows from top to bottom without any padding
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// copies textures into a buffer and reads them back without waiting on the GPU
+///
+/// the GPU copies rows padded to [padded_bytes_per_row], the [Frame]s read back are tightly packed again.
+/// only one copy is in flight at a time, [FrameReadback::is_idle] tells when the next one can be recorded
+pub struct FrameReadback {
+    /// reused as long as the size stays the same
+    buffer: Option<wgpu::Buffer>,
+    width: u32,
+    height: u32,
+    /// the bytes are stored as BGRA and get swapped
+    bgra: bool,
+    /// a copy was recorded and not read yet
+    pending: bool,
+    /// the copy was only recorded, the buffer gets mapped once it's submitted
+    needs_map: bool,
+    /// set by the map callback
+    mapped: Arc<AtomicBool>,
+}
+
+impl Default for FrameReadback {
+    fn default() -> Self {
+        FrameReadback {
+            buffer: None,
+            width: 0,
+            height: 0,
+            bgra: false,
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl FrameReadback {
+    /// only 8 bit RGBA and BGRA formats can be read back
+    pub fn supports(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb | wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        )
+    }
+
+    pub fn is_idle(&self) -> bool {
+        !self.pending
+    }
+
+    /// records copying `texture`, which needs [wgpu::TextureUsages::COPY_SRC] and a [FrameReadback::supports] format
+    pub fn copy(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture, width: u32, height: u32, format: wgpu::TextureFormat) {
+        debug_assert!(self.is_idle() && Self::supports(format));
+
+        let padded_bytes_per_row = padded_bytes_per_row(width);
+
+        if self.buffer.is_none() || (width, height) != (self.width, self.height) {
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback Buffer"),
+                size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.width = width;
+        self.height = height;
+        self.bgra = matches!(format, wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb);
+        self.pending = true;
+        self.needs_map = true;
+    }
+
+    /// called after the encoder passed to [FrameReadback::copy] was submitted
+    pub fn submitted(&mut self) {
+        let Some(buffer) = self.buffer.as_ref().filter(|_| self.needs_map) else {
+            return;
+        };
+
+        self.needs_map = false;
+
+        let mapped = self.mapped.clone();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            match result {
+                Ok(()) => mapped.store(true, Ordering::Release),
+                Err(err) => log::warn!("couldn't read back the frame: {}", err),
+            }
+        });
+    }
+
+    /// the copied frame, once the GPU finished the copy
+    pub fn read(&mut self, device: &wgpu::Device) -> Option<Frame> {
+        if !self.pending || self.needs_map {
+            return None;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return None;
+        }
+
+        let buffer = self.buffer.as_ref()?;
+        let mut rgba = strip_padding(&buffer.slice(..).get_mapped_range(), self.width);
+
+        buffer.unmap();
+        self.pending = false;
+
+        if self.bgra {
+            for pixel in rgba.chunks_exact_mut(BYTES_PER_PIXEL as usize) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Some(Frame {
+            width: self.width,
+            height: self.height,
+            rgba,
+        })
+    }
+}
+
+/// the bytes of one row of a texture `width` pixels wide once copied into a buffer, padded to a multiple of
+/// [wgpu::COPY_BYTES_PER_ROW_ALIGNMENT]
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// the rows of a copied texture `width` pixels wide without their padding, see [padded_bytes_per_row]
+pub fn strip_padding(padded: &[u8], width: u32) -> Vec<u8> {
+    let row_length = (width * BYTES_PER_PIXEL) as usize;
+    let padded_row_length = padded_bytes_per_row(width) as usize;
+
+    let mut rgba = Vec::with_capacity(padded.len() / padded_row_length * row_length);
+
+    for row in padded.chunks_exact(padded_row_length) {
+        rgba.extend_from_slice(&row[..row_length]);
+    }
+
+    rgba
+}
+
+/// shared between a [CaptureRequest] and its [FrameCapture]
+#[derive(Default)]
+struct CaptureSlot {
+    rgba: Option<Vec<u8>>,
+    waker: Option<Waker>,
+}
+
+/// a frame promised by [State::capture_frame](crate::state::State::capture_frame), resolves to its RGBA bytes
+pub struct FrameCapture(Arc<Mutex<CaptureSlot>>);
+
+/// the end of a [FrameCapture] kept until the frame was read back
+pub(crate) struct CaptureRequest(Arc<Mutex<CaptureSlot>>);
+
+pub(crate) fn capture_request() -> (CaptureRequest, FrameCapture) {
+    let slot = Arc::new(Mutex::new(CaptureSlot::default()));
+
+    (CaptureRequest(slot.clone()), FrameCapture(slot))
+}
+
+impl CaptureRequest {
+    pub(crate) fn fulfill(self, rgba: Vec<u8>) {
+        if let Ok(mut slot) = self.0.lock() {
+            slot.rgba = Some(rgba);
+        }
+    }
+}
+
+/// a request dropped without a frame resolves to no bytes instead of never
+impl Drop for CaptureRequest {
+    fn drop(&mut self) {
+        if let Ok(mut slot) = self.0.lock() {
+            slot.rgba.get_or_insert_with(Vec::new);
+
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Future for FrameCapture {
+    type Output = Vec<u8>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Vec<u8>> {
+        let Ok(mut slot) = self.0.lock() else {
+            return Poll::Ready(Vec::new());
+        };
+
+        match slot.rgba.take() {
+            Some(rgba) => Poll::Ready(rgba),
+            None => {
+                slot.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
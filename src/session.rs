@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::InputEvent;
+use crate::vec2::Vec2;
+
+/// everything needed to replay a run frame for frame
+///
+/// the remaining flags (boid count, threads, ...) have to match the recorded run
+/// and the control panel stays hidden while recording or replaying, changes made in it are not recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub seed: u64,
+    /// half width and height of the world at the start, later resizes are recorded as [InputEvent::WorldResized]
+    #[serde(default)]
+    pub world_bounds: Option<(f32, f32)>,
+    /// the `--update-rate` of the recorded run
+    #[serde(default)]
+    pub update_rate: Option<f32>,
+    /// events together with the frame they were applied in
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> io::Result<Session> {
+        let reader = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    session: Session,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf, seed: u64, world_bounds: &Vec2, update_rate: Option<f32>) -> Recorder {
+        Recorder {
+            path,
+            session: Session {
+                seed,
+                world_bounds: Some((world_bounds.x, world_bounds.y)),
+                update_rate,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, event: &InputEvent) {
+        self.session.events.push((frame, event.clone()));
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.session.save(&self.path)
+    }
+}
+
+pub struct Replay {
+    session: Session,
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(session: Session) -> Replay {
+        Replay {
+            session,
+            next: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.session.seed
+    }
+
+    /// [None] for sessions recorded before the world bounds were
+    pub fn world_bounds(&self) -> Option<Vec2> {
+        self.session.world_bounds.map(|(x, y)| Vec2::new(x, y))
+    }
+
+    pub fn update_rate(&self) -> Option<f32> {
+        self.session.update_rate
+    }
+
+    /// the events which have been recorded up to `frame` and not been returned yet
+    pub fn events_until(&mut self, frame: u64) -> Vec<InputEvent> {
+        let start = self.next;
+
+        while self.session.events.get(self.next).is_some_and(|(event_frame, _)| *event_frame <= frame) {
+            self.next += 1;
+        }
+
+        self.session.events[start..self.next].iter().map(|(_, event)| event.clone()).collect()
+    }
+}
@@ -1,13 +1,23 @@
 // Vertex shader
 
+struct Camera {
+    scale: vec2<f32>,
+    center: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
 };
 
 @vertex
@@ -17,7 +27,8 @@ fn vs_main(
     var out: VertexOutput;
 
     out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.uv = model.uv;
+    out.clip_position = vec4<f32>((model.position.xy - camera.center) * camera.scale, model.position.z, 1.0);
 
     return out;
 }
@@ -26,5 +37,48 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    return in.color;
+}
+
+// draws a circle into a quad whose uvs span 0..1, the edge is smoothed over about a pixel
+@fragment
+fn fs_circle(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.uv * 2.0 - 1.0) - 1.0;
+    let alpha = 1.0 - smoothstep(-fwidth(distance), 0.0, distance);
+
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(in.color.rgb, in.color.a * alpha);
+}
+
+// the sprite, or the boids rendered at a lower resolution when blitting
+@group(1) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var color_sampler: sampler;
+
+@fragment
+fn fs_sprite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(color_texture, color_sampler, in.uv) * in.color;
+}
+
+// a single triangle covering the whole screen, everything outside of it gets clipped
+@vertex
+fn vs_blit(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+
+    out.color = vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    out.uv = uv;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(color_texture, color_sampler, in.uv);
 }
@@ -0,0 +1,130 @@
+use std::f32::consts::PI;
+
+use crate::vec2::Vec2;
+use crate::vertex::Vertex;
+
+/// appends a filled circle as a triangle fan with `segments` outer vertices
+pub fn push_circle(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, center: &Vec2, radius: f32, segments: u32, color: [f32; 4]) {
+    push_ellipse(vertices, indices, center, (radius, radius), 0.0, segments, color);
+}
+
+/// appends a filled ellipse with the given radii, rotated by `angle`, as a triangle fan with `segments` outer vertices
+///
+/// the shape is built around the origin with the first radius along x and only then rotated and moved to `center`
+pub fn push_ellipse(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, center: &Vec2, radii: (f32, f32), angle: f32, segments: u32, color: [f32; 4]) {
+    let base = vertices.len() as u32;
+
+    let (sin, cos) = angle.sin_cos();
+
+    for i in 0..segments {
+        let segment_angle = ((PI * 2.0) / segments as f32) * i as f32;
+        let x = segment_angle.cos() * radii.0;
+        let y = segment_angle.sin() * radii.1;
+
+        vertices.push(Vertex {
+            position: [center.x + x * cos - y * sin, center.y + x * sin + y * cos, 0.0],
+            color,
+            uv: [0.0, 0.0],
+        });
+    }
+
+    for i in 0..(segments - 2) {
+        indices.push(base);
+        indices.push(base + i + 1);
+        indices.push(base + i + 2);
+    }
+}
+
+/// appends a quad centered at `center` with the given half extents, rotated by `angle`
+///
+/// the uv coordinates span the whole quad with u pointing along `angle`
+pub fn push_quad(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, center: &Vec2, half_extents: (f32, f32), angle: f32, color: [f32; 4]) {
+    let base = vertices.len() as u32;
+
+    let (sin, cos) = angle.sin_cos();
+
+    for (x, y, uv) in [(-1.0, -1.0, [0.0, 1.0]), (1.0, -1.0, [1.0, 1.0]), (1.0, 1.0, [1.0, 0.0]), (-1.0, 1.0, [0.0, 0.0])] {
+        let x = x * half_extents.0;
+        let y = y * half_extents.1;
+
+        vertices.push(Vertex {
+            position: [center.x + x * cos - y * sin, center.y + x * sin + y * cos, 0.0],
+            color,
+            uv,
+        });
+    }
+
+    indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+}
+
+/// appends an axis aligned strip from `min` to `max` whose color runs along x through `gradient(0..=1)`
+pub fn push_gradient_strip(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, min: &Vec2, max: &Vec2, segments: u32, gradient: impl Fn(f32) -> [f32; 4]) {
+    let base = vertices.len() as u32;
+
+    for i in 0..=segments {
+        let t = i as f32 / segments as f32;
+        let x = min.x + (max.x - min.x) * t;
+        let color = gradient(t);
+
+        vertices.push(Vertex { position: [x, min.y, 0.0], color, uv: [t, 1.0] });
+        vertices.push(Vertex { position: [x, max.y, 0.0], color, uv: [t, 0.0] });
+    }
+
+    for i in 0..segments {
+        let bottom = base + i * 2;
+        indices.extend_from_slice(&[bottom, bottom + 2, bottom + 3, bottom, bottom + 3, bottom + 1]);
+    }
+}
+
+/// appends a single segment for line list rendering
+pub fn push_line(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, from: &Vec2, to: &Vec2, color: [f32; 4]) {
+    let base = vertices.len() as u32;
+
+    for point in [from, to] {
+        vertices.push(Vertex {
+            position: [point.x, point.y, 0.0],
+            color,
+            uv: [0.0, 0.0],
+        });
+    }
+
+    indices.push(base);
+    indices.push(base + 1);
+}
+
+/// a line from `from` to `to` with a head at `to`, for line list rendering
+pub fn push_arrow(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, from: &Vec2, to: &Vec2, color: [f32; 4]) {
+    push_line(vertices, indices, from, to, color);
+
+    let mut direction = to.clone();
+    direction.sub(from);
+
+    let head_length = direction.length() * 0.25;
+    let angle = direction.angle();
+
+    for side in [-1.0, 1.0] {
+        let mut head = Vec2::from_angle(angle + PI + side * PI / 6.0);
+        head.mul(head_length);
+        head.add(to);
+
+        push_line(vertices, indices, to, &head, color);
+    }
+}
+
+/// appends the closed outline through `points` as line list segments
+pub fn push_line_loop(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, points: &[Vec2], color: [f32; 4]) {
+    let base = vertices.len() as u32;
+
+    for point in points {
+        vertices.push(Vertex {
+            position: [point.x, point.y, 0.0],
+            color,
+            uv: [0.0, 0.0],
+        });
+    }
+
+    for i in 0..points.len() as u32 {
+        indices.push(base + i);
+        indices.push(base + (i + 1) % points.len() as u32);
+    }
+}
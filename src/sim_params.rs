@@ -0,0 +1,441 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::boundary::Boundary;
+use crate::vec2::Vec2;
+
+pub const MIN_PERCEPTION_RADIUS: f32 = 0.005;
+pub const MAX_PERCEPTION_RADIUS: f32 = 0.2;
+
+/// fields missing when deserializing take their default value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimParams {
+    pub perception_radius: f32,
+    /// only neighbors closer than this push apart, alignment and cohesion use the whole perception radius
+    ///
+    /// capped by `perception_radius`
+    pub separation_radius: f32,
+    pub separation_weight: f32,
+    pub alignment_weight: f32,
+    pub alignment_mode: AlignmentMode,
+    pub cohesion_weight: f32,
+    /// boids with fewer neighbors skip cohesion, the center of only one or two of them jumps around and flings boids at the edge of the flock
+    pub min_neighbors_for_cohesion: usize,
+    /// the same for alignment, 0 always aligns
+    pub min_neighbors_for_alignment: usize,
+    /// how sharply each rule favors close neighbors, a neighbor counts `(1 - distance / radius)^exponent`
+    ///
+    /// 0 weights all neighbors the same, which is also the cheapest as no distances are needed.
+    /// negative ones would weigh neighbors at the radius infinitely, see [SimParams::validate]
+    pub separation_exponent: i32,
+    pub alignment_exponent: i32,
+    pub cohesion_exponent: i32,
+    /// only the closest `max_neighbors` are used for steering, bounding the work in dense clusters
+    ///
+    /// this is an approximation, a boid in a cluster ignores everything beyond its closest neighbors
+    pub max_neighbors: Option<usize>,
+
+    /// distance a boid moves per update
+    pub max_speed: f32,
+    /// strength of the random steering applied every update
+    pub jitter: f32,
+    pub jitter_mode: JitterMode,
+    /// strength of the [JitterMode::Coherent] steering, used instead of `jitter`
+    pub wander_strength: f32,
+    /// fraction of the speed lost per update, boids only keep moving at full speed while steering
+    pub drag: f32,
+    /// share of the previous heading kept every update, 0 turns fully towards the steering and values near 1 turn slowly
+    pub smoothing: f32,
+    /// every update is integrated in this many smaller steps, fast boids then follow their steering more closely
+    ///
+    /// each step searches the neighbors again, so the cost grows linearly
+    pub substeps: u32,
+
+    /// how strongly boids get pulled towards the goal of the simulation
+    pub goal_weight: f32,
+    /// how strongly followers get pulled towards their nearest leader
+    pub leader_weight: f32,
+    /// how strongly leaders steer towards their target
+    pub leader_target_weight: f32,
+
+    /// pushes overlapping boids apart after every step
+    pub resolve_collisions: bool,
+
+    /// boids are removed once they are this many updates old, [None] lets them live forever
+    pub max_age: Option<u32>,
+    /// boids spawned per update next to a random existing one, fractions carry over to the next update
+    pub birth_rate: f32,
+    /// no boids are born while there are this many
+    pub max_population: usize,
+
+    pub spawn_speed: SpawnSpeed,
+    pub spawn_pattern: SpawnPattern,
+    /// direction spawned boids head in, in radians counterclockwise from the x axis
+    pub spawn_heading: f32,
+    /// spawned boids deviate up to this many radians from `spawn_heading`, [PI] is fully random
+    pub spawn_heading_spread: f32,
+
+    pub boundary_mode: BoundaryMode,
+    /// distance from the world border at which boids start to get pushed back
+    pub edge_margin: f32,
+    /// 0 turns the push back off, `center_pull` alone then keeps the flock around
+    pub edge_force: f32,
+    /// pull towards the world center growing with the distance to it, a soft alternative to the edge push back
+    pub center_pull: f32,
+    /// the world spans from `-world_bounds` to `world_bounds` on each axis
+    #[serde(skip)]
+    pub world_bounds: Vec2,
+    /// the area boids are kept in, [None] uses the whole world
+    #[serde(skip)]
+    pub boundary: Option<Arc<dyn Boundary>>,
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        SimParams {
+            perception_radius: 0.03,
+            separation_radius: 0.03,
+            separation_weight: 2.0,
+            alignment_weight: 0.5,
+            alignment_mode: AlignmentMode::Heading,
+            cohesion_weight: 0.6,
+            min_neighbors_for_cohesion: 0,
+            min_neighbors_for_alignment: 0,
+            separation_exponent: 3,
+            alignment_exponent: 0,
+            cohesion_exponent: 0,
+            max_neighbors: None,
+
+            max_speed: 0.005,
+            jitter: 0.2,
+            jitter_mode: JitterMode::White,
+            wander_strength: 0.4,
+            drag: 0.0,
+            smoothing: 0.0,
+            substeps: 1,
+
+            goal_weight: 0.1,
+            leader_weight: 0.3,
+            leader_target_weight: 0.5,
+
+            resolve_collisions: false,
+
+            max_age: None,
+            birth_rate: 0.0,
+            max_population: 50000,
+
+            spawn_speed: SpawnSpeed::default(),
+            spawn_pattern: SpawnPattern::Disc,
+            spawn_heading: 0.0,
+            spawn_heading_spread: PI,
+
+            boundary_mode: BoundaryMode::Bounce,
+            edge_margin: 0.2,
+            edge_force: 1.0,
+            center_pull: 0.0,
+            world_bounds: Vec2::new(1.0, 1.0),
+            boundary: None,
+        }
+    }
+}
+
+/// one of the three flocking rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Separation,
+    Alignment,
+    Cohesion,
+}
+
+impl Rule {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::Separation => "separation",
+            Rule::Alignment => "alignment",
+            Rule::Cohesion => "cohesion",
+        }
+    }
+}
+
+/// what alignment averages over the neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlignmentMode {
+    /// only the direction, every neighbor counts the same no matter how fast it is
+    Heading,
+    /// the heading scaled by the speed, fast neighbors count more and slow ones barely turn anyone
+    Velocity,
+}
+
+/// what happens to boids reaching the border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    /// pushed back with [SimParams::edge_force] once within [SimParams::edge_margin] of the boundary
+    Bounce,
+    /// leaving the world on one side enters it on the opposite one, the boundary is ignored
+    ///
+    /// neighbors aren't searched across the border, so flocks split up while they pass it
+    Wrap,
+}
+
+impl BoundaryMode {
+    pub fn next(self) -> BoundaryMode {
+        match self {
+            BoundaryMode::Bounce => BoundaryMode::Wrap,
+            BoundaryMode::Wrap => BoundaryMode::Bounce,
+        }
+    }
+
+    /// how much of the edge push back is applied in this mode
+    pub fn edge_force_factor(self) -> f32 {
+        match self {
+            BoundaryMode::Bounce => 1.0,
+            BoundaryMode::Wrap => 0.0,
+        }
+    }
+}
+
+/// the kind of random steering boids get on top of the flocking rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JitterMode {
+    /// a new random direction every update, boids twitch on the spot but keep their overall course
+    White,
+    /// a noise field changing slowly over space and time, nearby boids drift the same way and curve in long arcs
+    Coherent,
+}
+
+/// the speed spawned boids start with as a fraction of [SimParams::max_speed], steering speeds them up again
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpawnSpeed {
+    Constant(f32),
+    /// uniformly distributed between the two values
+    Uniform(f32, f32),
+}
+
+/// where spawned boids are placed and how they are colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnPattern {
+    /// a small disc around the center, all white
+    Disc,
+    /// spread over the whole world in horizontal rainbow bands, showing how the flock mixes
+    ColorBands,
+}
+
+impl FromStr for SpawnPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disc" => Ok(SpawnPattern::Disc),
+            "bands" => Ok(SpawnPattern::ColorBands),
+            _ => Err(format!("unknown spawn pattern '{}', expected disc or bands", s)),
+        }
+    }
+}
+
+impl Default for SpawnSpeed {
+    fn default() -> Self {
+        SpawnSpeed::Constant(1.0)
+    }
+}
+
+impl SpawnSpeed {
+    pub fn sample(self, rng: &mut impl Rng) -> f32 {
+        match self {
+            SpawnSpeed::Constant(speed) => speed,
+            SpawnSpeed::Uniform(min, max) if min < max => rng.gen_range(min..max),
+            SpawnSpeed::Uniform(min, _) => min,
+        }
+    }
+}
+
+/// either a single speed like `0.5` or a range like `0.2..1.0`, both within 0 to 1
+impl FromStr for SpawnSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            let speed: f32 = value.trim().parse().map_err(|_| format!("invalid speed '{}'", value))?;
+
+            if (0.0..=1.0).contains(&speed) {
+                Ok(speed)
+            } else {
+                Err(format!("speed '{}' is not within 0 to 1", value))
+            }
+        };
+
+        match s.split_once("..") {
+            Some((min, max)) => {
+                let (min, max) = (parse(min)?, parse(max)?);
+
+                if min > max {
+                    return Err(format!("'{}' is an empty range", s));
+                }
+
+                Ok(SpawnSpeed::Uniform(min, max))
+            }
+            None => Ok(SpawnSpeed::Constant(parse(s)?)),
+        }
+    }
+}
+
+impl SimParams {
+    /// blends the numeric parameters from `self` at 0 to `other` at 1
+    ///
+    /// everything that can't be blended, as well as the world, is taken from `self`
+    pub fn lerp(&self, other: &SimParams, t: f32) -> SimParams {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+
+        SimParams {
+            perception_radius: lerp(self.perception_radius, other.perception_radius),
+            separation_radius: lerp(self.separation_radius, other.separation_radius),
+            separation_weight: lerp(self.separation_weight, other.separation_weight),
+            alignment_weight: lerp(self.alignment_weight, other.alignment_weight),
+            cohesion_weight: lerp(self.cohesion_weight, other.cohesion_weight),
+            max_speed: lerp(self.max_speed, other.max_speed),
+            jitter: lerp(self.jitter, other.jitter),
+            wander_strength: lerp(self.wander_strength, other.wander_strength),
+            drag: lerp(self.drag, other.drag),
+            smoothing: lerp(self.smoothing, other.smoothing),
+            goal_weight: lerp(self.goal_weight, other.goal_weight),
+            leader_weight: lerp(self.leader_weight, other.leader_weight),
+            leader_target_weight: lerp(self.leader_target_weight, other.leader_target_weight),
+            edge_margin: lerp(self.edge_margin, other.edge_margin),
+            edge_force: lerp(self.edge_force, other.edge_force),
+            center_pull: lerp(self.center_pull, other.center_pull),
+            birth_rate: lerp(self.birth_rate, other.birth_rate),
+            ..self.clone()
+        }
+    }
+
+    /// checks parameters that can't be limited by their type, like ones loaded from a timeline
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, exponent) in [("separation_exponent", self.separation_exponent), ("alignment_exponent", self.alignment_exponent), ("cohesion_exponent", self.cohesion_exponent)] {
+            if exponent < 0 {
+                return Err(format!("'{}' must not be negative, got {}", name, exponent));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the weights of separation, alignment and cohesion
+    pub fn weights(&self) -> (f32, f32, f32) {
+        (self.separation_weight, self.alignment_weight, self.cohesion_weight)
+    }
+
+    pub fn set_weights(&mut self, (separation, alignment, cohesion): (f32, f32, f32)) {
+        self.separation_weight = separation;
+        self.alignment_weight = alignment;
+        self.cohesion_weight = cohesion;
+    }
+
+    /// keeps the weight of `rule` out of `weights` and zeroes the other two
+    pub fn isolate(&mut self, rule: Rule, weights: (f32, f32, f32)) {
+        let (separation, alignment, cohesion) = weights;
+
+        self.set_weights(match rule {
+            Rule::Separation => (separation, 0.0, 0.0),
+            Rule::Alignment => (0.0, alignment, 0.0),
+            Rule::Cohesion => (0.0, 0.0, cohesion),
+        });
+    }
+
+    /// the area covered by the spatial index, slightly larger than the world as boids can overshoot the edges
+    pub fn index_range(&self) -> (Range<f64>, Range<f64>) {
+        let x = self.world_bounds.x as f64 * 1.1;
+        let y = self.world_bounds.y as f64 * 1.1;
+
+        (-x..x, -y..y)
+    }
+}
+
+/// a named flocking style, only covers the fields that change how the flock looks
+#[derive(Debug, Clone, Copy)]
+pub struct Preset {
+    pub name: &'static str,
+    pub perception_radius: f32,
+    pub separation_radius: f32,
+    pub separation_weight: f32,
+    pub alignment_weight: f32,
+    pub cohesion_weight: f32,
+    pub max_speed: f32,
+    pub jitter: f32,
+    pub smoothing: f32,
+}
+
+/// what the preset key cycles through, the first one matches [SimParams::default]
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "Default",
+        perception_radius: 0.03,
+        separation_radius: 0.03,
+        separation_weight: 2.0,
+        alignment_weight: 0.5,
+        cohesion_weight: 0.6,
+        max_speed: 0.005,
+        jitter: 0.2,
+        smoothing: 0.0,
+    },
+    Preset {
+        name: "Tight School",
+        perception_radius: 0.05,
+        separation_radius: 0.015,
+        separation_weight: 1.5,
+        alignment_weight: 1.5,
+        cohesion_weight: 1.2,
+        max_speed: 0.005,
+        jitter: 0.05,
+        smoothing: 0.3,
+    },
+    Preset {
+        name: "Loose Swarm",
+        perception_radius: 0.06,
+        separation_radius: 0.04,
+        separation_weight: 2.5,
+        alignment_weight: 0.2,
+        cohesion_weight: 0.3,
+        max_speed: 0.004,
+        jitter: 0.4,
+        smoothing: 0.0,
+    },
+    Preset {
+        name: "Nervous",
+        perception_radius: 0.03,
+        separation_radius: 0.03,
+        separation_weight: 3.0,
+        alignment_weight: 0.8,
+        cohesion_weight: 0.4,
+        max_speed: 0.009,
+        jitter: 0.8,
+        smoothing: 0.0,
+    },
+    Preset {
+        name: "Lazy",
+        perception_radius: 0.04,
+        separation_radius: 0.02,
+        separation_weight: 1.0,
+        alignment_weight: 0.6,
+        cohesion_weight: 0.8,
+        max_speed: 0.002,
+        jitter: 0.1,
+        smoothing: 0.7,
+    },
+];
+
+impl Preset {
+    /// overwrites the fields covered by the preset, everything else in `params` is kept
+    pub fn apply(&self, params: &mut SimParams) {
+        params.perception_radius = self.perception_radius;
+        params.separation_radius = self.separation_radius;
+        params.set_weights((self.separation_weight, self.alignment_weight, self.cohesion_weight));
+        params.max_speed = self.max_speed;
+        params.jitter = self.jitter;
+        params.smoothing = self.smoothing;
+    }
+}
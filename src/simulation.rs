@@ -0,0 +1,847 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+
+use instant::Instant;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use spatial_neighbors::quad_tree::QuadTree;
+use spatial_neighbors::SpatialPartitioner;
+
+use crate::boid::{Boid, SIZE};
+use crate::brush::Brush;
+use crate::sim_params::SimParams;
+use crate::snapshot::{BoidState, Snapshot};
+use crate::spatial::{in_circle_into, in_rect_into, Nearest};
+use crate::stats::{NeighborHistogram, TimingWindow};
+use crate::steering::{compute_steering, default_behaviors, SteeringBehavior};
+use crate::trails::Trails;
+use crate::vec2::Vec2;
+
+/// how far from their parent new boids get born
+const BIRTH_DISTANCE: f32 = 0.01;
+
+/// for 20000 boids the index_capacity bench puts 25 to 75 within noise of each other, 10 and 150 are slower
+pub const DEFAULT_INDEX_CAPACITY: u16 = 75;
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 2000;
+/// the most steps a single update is split into at high time scales, on top of [SimParams::substeps]
+pub const MAX_SUBSTEPS: u32 = 8;
+/// updates the edge push back takes to fade in or out after switching the [BoundaryMode](crate::sim_params::BoundaryMode)
+///
+/// boids turning away from the edge keep turning a little longer instead of all changing course at once
+const BOUNDARY_TRANSITION: f32 = 60.0;
+
+/// what a single boid steers by, see [Simulation::inspect]
+pub struct Inspection {
+    pub neighbors: Vec<usize>,
+    /// the weighted steering of each of the [Simulation::behaviors] in the same order
+    pub steering: Vec<Vec2>,
+}
+
+/// the flock itself, independent of any window or GPU
+pub struct Simulation {
+    boids: Vec<Boid>,
+    boid_count: usize,
+    pub params: SimParams,
+    /// a point all boids steer towards
+    pub goal: Option<Vec2>,
+    /// where leaders head, [None] lets them follow a built in path
+    pub leader_target: Option<Vec2>,
+
+    /// simulated time per update, above 1 the update is split into multiple steps
+    pub time_scale: f32,
+
+    /// the result doesn't depend on it, threads only read the boids and the rng is only used after joining them
+    pub thread_count: usize,
+    /// below this boid count the update runs sequentially as spawning threads costs more than it saves
+    pub parallel_threshold: usize,
+    /// boids per quad-tree node before it splits, small values make deep trees and large ones long scans per node
+    pub index_capacity: u16,
+    /// splits the boids between threads by their neighbor count in the last step instead of into equal ranges
+    ///
+    /// boids in dense clusters take far longer to steer, with equal ranges one thread ends up finishing late
+    pub balance_threads: bool,
+    /// neighbors of every boid in the last step, the estimated steering cost for [Simulation::balance_threads]
+    neighbor_counts: Vec<u32>,
+
+    seed: u64,
+    /// updates since the simulation was created or reset
+    frame: u64,
+    // the same generator as `StdRng`, but its state can be serialized
+    rng: ChaCha12Rng,
+
+    /// the index built in the last update, [None] whenever boids were added or removed since
+    index: Option<Arc<QuadTree<usize>>>,
+    /// keeps using the last index instead of rebuilding it, neighbors are looked up at stale positions
+    ///
+    /// only meant to time steering without the index build
+    pub freeze_index: bool,
+    /// the index is rebuilt every this many steps and reused in between, 1 rebuilds it every step
+    ///
+    /// slow flocks barely move between rebuilds, so the stale positions cost little accuracy
+    pub rebuild_interval: u32,
+    /// steps since the current index was built
+    index_age: u32,
+    /// the fraction of a boid left over from the last births
+    pending_births: f32,
+    /// share of the edge push back currently applied, follows [SimParams::boundary_mode] over [BOUNDARY_TRANSITION] updates
+    edge_factor: f32,
+
+    /// summed up to the steering of every boid, starts out as separation, alignment and cohesion
+    pub behaviors: Vec<Arc<dyn SteeringBehavior>>,
+
+    /// recent positions of every boid for drawing their paths, [None] skips recording them
+    pub trails: Option<Trails>,
+
+    pub index_time: TimingWindow,
+    pub steer_time: TimingWindow,
+    pub write_back_time: TimingWindow,
+    /// steering time of the slowest and of the average thread, equal when running sequentially
+    pub slowest_thread_time: TimingWindow,
+    pub mean_thread_time: TimingWindow,
+}
+
+/// milliseconds spent in the parts of a single step
+struct StepTimes {
+    index: f64,
+    steer: f64,
+    write_back: f64,
+    slowest_thread: f64,
+    mean_thread: f64,
+}
+
+impl Simulation {
+    pub fn new(boid_count: usize, params: SimParams, seed: u64) -> Simulation {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let boids = Self::spawn_boids(boid_count, &params, &mut rng);
+        let edge_factor = params.boundary_mode.edge_force_factor();
+
+        Simulation {
+            boids,
+            boid_count,
+            params,
+            goal: None,
+            leader_target: None,
+
+            time_scale: 1.0,
+
+            thread_count: num_cpus::get(),
+            parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            index_capacity: DEFAULT_INDEX_CAPACITY,
+            balance_threads: true,
+            neighbor_counts: Vec::new(),
+
+            seed,
+            frame: 0,
+            rng,
+
+            index: None,
+            freeze_index: false,
+            rebuild_interval: 1,
+            index_age: 0,
+            pending_births: 0.0,
+            edge_factor,
+            behaviors: default_behaviors(),
+
+            trails: None,
+
+            index_time: TimingWindow::default(),
+            steer_time: TimingWindow::default(),
+            write_back_time: TimingWindow::default(),
+            slowest_thread_time: TimingWindow::default(),
+            mean_thread_time: TimingWindow::default(),
+        }
+    }
+
+    /// starts with exactly `boids` instead of random ones, e.g. two boids, a line or a ring to check steering on
+    ///
+    /// everything random later on, like jitter and births, still comes from `seed` and no thread count changes the results.
+    /// [Simulation::reset] respawns random boids though
+    pub fn new_deterministic(boids: Vec<Boid>, params: SimParams, seed: u64) -> Simulation {
+        // spawning no boids doesn't draw from the rng, so it starts out the same as for any other seeded simulation
+        let mut simulation = Simulation::new(0, params, seed);
+        simulation.set_boids(boids);
+
+        simulation
+    }
+
+    fn spawn_boids(count: usize, params: &SimParams, rng: &mut ChaCha12Rng) -> Vec<Boid> {
+        let mut boids = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            boids.push(Boid::new_random(rng, params));
+        }
+
+        boids
+    }
+
+    /// respawns all boids and forgets the timings measured so far
+    pub fn reset(&mut self) {
+        self.boids = Self::spawn_boids(self.boid_count, &self.params, &mut self.rng);
+        self.frame = 0;
+        self.index = None;
+        self.clear_trails();
+
+        self.reset_timings();
+    }
+
+    pub fn reset_timings(&mut self) {
+        self.index_time.clear();
+        self.steer_time.clear();
+        self.write_back_time.clear();
+        self.slowest_thread_time.clear();
+        self.mean_thread_time.clear();
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// steps the index used in the last update was behind the boids, 0 if it was built for them
+    pub fn index_age(&self) -> u32 {
+        self.index_age
+    }
+
+    /// whether the edge push back is still fading in or out after the [BoundaryMode](crate::sim_params::BoundaryMode) was switched
+    pub fn boundary_transitioning(&self) -> bool {
+        self.edge_factor != self.params.boundary_mode.edge_force_factor()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            seed: self.seed,
+            frame: self.frame,
+            boids: self.boids.iter()
+                .map(|boid| BoidState {
+                    location: (boid.location.x, boid.location.y),
+                    vel: (boid.vel.x, boid.vel.y),
+                    scale: boid.scale,
+                    is_leader: boid.is_leader,
+                    speed: boid.speed,
+                    pinned: boid.pinned,
+                    age: boid.age,
+                    color: boid.color,
+                })
+                .collect(),
+            rng: self.rng.clone(),
+            pending_births: self.pending_births,
+            edge_factor: self.edge_factor,
+            index_age: self.index_age,
+            neighbor_counts: self.neighbor_counts.clone(),
+        }
+    }
+
+    /// replaces all boids with the ones from `snapshot`, the parameters are kept
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.boids = snapshot.boids.iter()
+            .map(|boid| Boid {
+                scale: boid.scale,
+                is_leader: boid.is_leader,
+                speed: boid.speed,
+                pinned: boid.pinned,
+                age: boid.age,
+                color: boid.color,
+                ..Boid::new(Vec2::new(boid.location.0, boid.location.1), Vec2::new(boid.vel.0, boid.vel.1))
+            })
+            .collect();
+        self.boid_count = self.boids.len();
+        self.index = None;
+        self.clear_trails();
+
+        self.seed = snapshot.seed;
+        self.frame = snapshot.frame;
+        self.rng = snapshot.rng.clone();
+        self.pending_births = snapshot.pending_births;
+        self.edge_factor = snapshot.edge_factor;
+        self.index_age = snapshot.index_age;
+        self.neighbor_counts = snapshot.neighbor_counts.clone();
+    }
+
+    /// replaces all boids, resetting or changing the boid count spawns random ones again
+    pub fn set_boids(&mut self, boids: Vec<Boid>) {
+        self.boids = boids;
+        self.boid_count = self.boids.len();
+        self.index = None;
+        self.clear_trails();
+    }
+
+    pub fn boid_count(&self) -> usize {
+        self.boid_count
+    }
+
+    /// spawns or removes boids until there are `count`, the remaining boids keep their state
+    pub fn set_boid_count(&mut self, count: usize) {
+        self.boid_count = count;
+        self.index = None;
+
+        if count < self.boids.len() {
+            self.boids.truncate(count);
+        } else {
+            let missing = count - self.boids.len();
+            self.boids.extend(Self::spawn_boids(missing, &self.params, &mut self.rng));
+        }
+    }
+
+    /// changes the size of the world, boids outside of the new bounds get moved onto its border
+    pub fn set_world_bounds(&mut self, world_bounds: Vec2) {
+        for boid in &mut self.boids {
+            boid.location.x = boid.location.x.clamp(-world_bounds.x, world_bounds.x);
+            boid.location.y = boid.location.y.clamp(-world_bounds.y, world_bounds.y);
+        }
+
+        self.params.world_bounds = world_bounds;
+    }
+
+    /// pushes every boid away from `center` proportional to its distance, a negative `strength` pulls them in
+    pub fn apply_radial_impulse(&mut self, center: &Vec2, strength: f32) {
+        for boid in self.boids.iter_mut().filter(|boid| !boid.pinned) {
+            let mut offset = boid.location.clone();
+            offset.sub(center);
+
+            boid.add_vel(&mut offset, strength);
+        }
+    }
+
+    /// pushes the boids under `brush`, called once per update while it's held
+    pub fn apply_brush(&mut self, brush: &Brush) {
+        for boid in self.boids.iter_mut().filter(|boid| !boid.pinned) {
+            if let Some(mut force) = brush.force(&boid.location) {
+                boid.add_vel(&mut force, 1.0);
+            }
+        }
+    }
+
+    /// the average location of all boids
+    pub fn centroid(&self) -> Option<Vec2> {
+        self.bounds_and_centroid().map(|(_, _, centroid)| centroid)
+    }
+
+    /// the lower left and upper right corner of the box around all boids, both at the origin without boids
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        self.bounds_and_centroid()
+            .map(|(min, max, _)| (min, max))
+            .unwrap_or((Vec2::ZERO, Vec2::ZERO))
+    }
+
+    /// [Simulation::bounds] and [Simulation::centroid] in a single pass, [None] without boids
+    pub fn bounds_and_centroid(&self) -> Option<(Vec2, Vec2, Vec2)> {
+        let first = &self.boids.first()?.location;
+
+        let mut min = first.clone();
+        let mut max = first.clone();
+        let mut centroid = Vec2::ZERO;
+
+        for boid in &self.boids {
+            min = Vec2::new(min.x.min(boid.location.x), min.y.min(boid.location.y));
+            max = Vec2::new(max.x.max(boid.location.x), max.y.max(boid.location.y));
+            centroid.add(&boid.location);
+        }
+
+        centroid.div(self.boids.len() as f32);
+
+        Some((min, max, centroid))
+    }
+
+    /// makes the first `count` boids leaders and all others followers
+    pub fn set_leader_count(&mut self, count: usize) {
+        for (index, boid) in self.boids.iter_mut().enumerate() {
+            boid.is_leader = index < count;
+        }
+    }
+
+    pub fn leader_count(&self) -> usize {
+        self.boids.iter().filter(|boid| boid.is_leader).count()
+    }
+
+    /// the point leaders steer towards when there is no [Simulation::leader_target]
+    fn leader_path(&self) -> Vec2 {
+        let t = self.frame as f32 * 0.01;
+
+        Vec2::new((t * 0.7).sin() * self.params.world_bounds.x * 0.6, t.sin() * self.params.world_bounds.y * 0.6)
+    }
+
+    pub fn boids(&self) -> &[Boid] {
+        &self.boids
+    }
+
+    /// uses the index of the last update if there is one, the boids have moved at least a step since
+    pub fn nearest_boid(&self, position: &Vec2) -> Option<usize> {
+        if let Some(index) = &self.index {
+            return index.nearest((position.x as f64, position.y as f64)).map(|(index, _)| index);
+        }
+
+        self.boids.iter()
+            .map(|boid| boid.location.distance_squared(position))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// the neighbors of boid `index` and what each behavior makes of them, using the index of the last update
+    pub fn inspect(&self, index: usize) -> Option<Inspection> {
+        let boid = self.boids.get(index)?;
+
+        let quad_tree = match &self.index {
+            Some(quad_tree) => quad_tree.clone(),
+            None => Arc::new(self.build_index()),
+        };
+
+        let mut neighbors = Vec::new();
+        find_neighbors(&self.boids, &quad_tree, &self.params, index, &mut neighbors);
+
+        let neighbor_boids: Vec<&Boid> = neighbors.iter().map(|neighbor_boid| &self.boids[*neighbor_boid]).collect();
+        let steering = self.behaviors.iter()
+            .map(|behavior| compute_steering(boid, index, &neighbor_boids, &neighbors, &self.params, std::slice::from_ref(behavior)))
+            .collect();
+
+        Some(Inspection { neighbors, steering })
+    }
+
+    pub fn toggle_pinned(&mut self, index: usize) {
+        if let Some(boid) = self.boids.get_mut(index) {
+            boid.pinned = !boid.pinned;
+        }
+    }
+
+    /// the indices of all boids within the rectangle spanned by `min` and `max`
+    pub fn boids_in_rect(&self, min: &Vec2, max: &Vec2) -> Vec<usize> {
+        // the index of the last update was built before the boids moved
+        let quad_tree = self.build_index();
+
+        let mut indices = Vec::new();
+        in_rect_into(&quad_tree, (min.x as f64, min.y as f64), (max.x as f64, max.y as f64), &mut indices);
+
+        indices
+    }
+
+    pub fn build_index(&self) -> QuadTree<usize> {
+        let (range_x, range_y) = self.params.index_range();
+        let mut quad_tree = QuadTree::with_capacity(range_x.clone(), range_y.clone(), self.index_capacity);
+
+        for (index, boid) in self.boids.iter().enumerate() {
+            // inserting outside of the range panics, a boundary reaching out of the world must not take the app down with it
+            let x = (boid.location.x as f64).clamp(range_x.start, range_x.end.next_down());
+            let y = (boid.location.y as f64).clamp(range_y.start, range_y.end.next_down());
+
+            quad_tree.insert((x, y), index);
+        }
+
+        quad_tree
+    }
+
+    /// mean distance of every boid to its closest neighbor, a rough measure of how tight the flock is
+    pub fn average_nearest_distance(&self) -> f32 {
+        if self.boids.len() < 2 {
+            return 0.0;
+        }
+
+        let quad_tree = self.build_index();
+
+        let sum: f64 = self.boids.iter()
+            .enumerate()
+            .filter_map(|(index, boid)| quad_tree.nearest_where((boid.location.x as f64, boid.location.y as f64), |other| other != index))
+            .map(|(_, distance)| distance)
+            .sum();
+
+        (sum / self.boids.len() as f64) as f32
+    }
+
+    /// how many neighbors each boid has, using the index of the last update
+    pub fn neighbor_histogram(&self) -> NeighborHistogram {
+        let quad_tree = match &self.index {
+            Some(quad_tree) => quad_tree.clone(),
+            None => Arc::new(self.build_index()),
+        };
+
+        let mut histogram = NeighborHistogram::default();
+        let mut neighbors = Vec::new();
+
+        for (index, boid) in self.boids.iter().enumerate() {
+            neighbors.clear();
+            in_circle_into(&quad_tree, (boid.location.x as f64, boid.location.y as f64), self.params.perception_radius as f64, &mut neighbors);
+
+            histogram.add(neighbors.iter().filter(|neighbor| **neighbor != index).count());
+        }
+
+        histogram
+    }
+
+    pub fn update(&mut self) {
+        let substeps = (self.time_scale.ceil() as u32).clamp(1, MAX_SUBSTEPS) * self.params.substeps.max(1);
+        let dt = self.time_scale / substeps as f32;
+
+        let mut index_time = 0.0;
+        let mut steer_time = 0.0;
+        let mut write_back_time = 0.0;
+        let mut slowest_thread_time = 0.0;
+        let mut mean_thread_time = 0.0;
+
+        for substep in 0..substeps {
+            let times = self.step(dt, self.frame as f32 + substep as f32 / substeps as f32);
+
+            index_time += times.index;
+            steer_time += times.steer;
+            write_back_time += times.write_back;
+            slowest_thread_time += times.slowest_thread;
+            mean_thread_time += times.mean_thread;
+        }
+
+        self.index_time.push(index_time);
+        self.steer_time.push(steer_time);
+        self.write_back_time.push(write_back_time);
+        self.slowest_thread_time.push(slowest_thread_time);
+        self.mean_thread_time.push(mean_thread_time);
+
+        self.frame += 1;
+
+        self.age_boids();
+
+        if let Some(trails) = &mut self.trails {
+            trails.record(&self.boids);
+        }
+    }
+
+    /// ages every boid by an update, removes the ones beyond [SimParams::max_age] and spawns new ones at [SimParams::birth_rate]
+    fn age_boids(&mut self) {
+        let count = self.boids.len();
+
+        for boid in &mut self.boids {
+            boid.age = boid.age.saturating_add(1);
+        }
+
+        if let Some(max_age) = self.params.max_age {
+            // pinned boids are placed deliberately, they stay until unpinned
+            self.boids.retain(|boid| boid.pinned || boid.age <= max_age);
+        }
+
+        // the survivors moved up to fill the gaps, births making up for the deaths don't put them back
+        let died = self.boids.len() != count;
+
+        if self.params.birth_rate > 0.0 {
+            self.pending_births += self.params.birth_rate;
+
+            while self.pending_births >= 1.0 && self.boids.len() < self.params.max_population {
+                self.pending_births -= 1.0;
+
+                let mut boid = Boid::new_random(&mut self.rng, &self.params);
+
+                // born next to a random parent, heading the same way in its color. an empty world spawns them like at the start
+                if !self.boids.is_empty() {
+                    let parent = &self.boids[self.rng.gen_range(0..self.boids.len())];
+
+                    let mut offset = Vec2::from_angle(self.rng.gen::<f32>() * PI * 2.0);
+                    offset.mul(self.rng.gen::<f32>() * BIRTH_DISTANCE);
+                    offset.add(&parent.location);
+
+                    boid.location = offset;
+                    boid.vel = parent.vel.clone();
+                    boid.color = parent.color;
+                }
+
+                self.boids.push(boid);
+            }
+
+            // a full population doesn't save up births for later
+            self.pending_births = self.pending_births.min(1.0);
+        }
+
+        if died || self.boids.len() != count {
+            self.index = None;
+        }
+    }
+
+    fn clear_trails(&mut self) {
+        if let Some(trails) = &mut self.trails {
+            trails.clear();
+        }
+    }
+
+    /// whether the steering gets split between threads, with too few boids or a single thread everything runs on one
+    pub fn runs_parallel(&self) -> bool {
+        self.thread_count > 1 && self.boids.len() >= self.parallel_threshold
+    }
+
+    /// contiguous ranges of boids for each thread, of about equal cost when balancing and equal length otherwise
+    fn thread_ranges(&self) -> Vec<Range<usize>> {
+        let boid_count = self.boids.len();
+
+        if !self.balance_threads || self.neighbor_counts.len() != boid_count {
+            let boids_per_thread = boid_count as f32 / self.thread_count as f32;
+
+            return (0..self.thread_count)
+                .map(|i| (boids_per_thread * i as f32).ceil() as usize..((boids_per_thread * (i + 1) as f32).ceil() as usize))
+                .collect();
+        }
+
+        // the neighbor search itself costs something even without neighbors
+        let total_cost: u64 = self.neighbor_counts.iter().map(|count| *count as u64 + 1).sum();
+
+        let mut ranges = Vec::with_capacity(self.thread_count);
+        let mut start = 0;
+        let mut cost = 0;
+
+        for (index, count) in self.neighbor_counts.iter().enumerate() {
+            cost += *count as u64 + 1;
+
+            let thread = ranges.len() as u64 + 1;
+            if ranges.len() + 1 < self.thread_count && cost * self.thread_count as u64 >= total_cost * thread {
+                ranges.push(start..index + 1);
+                start = index + 1;
+            }
+        }
+
+        ranges.push(start..boid_count);
+        ranges
+    }
+
+    /// advances all boids by `dt`, `time` is the number of updates so far including partial ones
+    fn step(&mut self, dt: f32, time: f32) -> StepTimes {
+        let start_time = Instant::now();
+
+        let quad_tree = match &self.index {
+            Some(index) if self.freeze_index || self.index_age + 1 < self.rebuild_interval => {
+                self.index_age += 1;
+                index.clone()
+            }
+            _ => {
+                self.index_age = 0;
+                Arc::new(self.build_index())
+            }
+        };
+
+        let index_time = start_time.elapsed().as_secs_f64() * 1000.0;
+        let start_time = Instant::now();
+
+        let results = if !self.runs_parallel() {
+            vec![timed_steer_range(&self.boids, &quad_tree, &self.params, &self.behaviors, 0..self.boids.len())]
+        } else {
+            let boids = Arc::new(self.boids.clone());
+
+            let mut threads = Vec::new();
+
+            for range in self.thread_ranges() {
+                let boids = boids.clone();
+                let quad_tree = quad_tree.clone();
+                let params = self.params.clone();
+
+                let behaviors = self.behaviors.clone();
+
+                threads.push(thread::spawn(move || timed_steer_range(&boids, &quad_tree, &params, &behaviors, range)));
+            }
+
+            let mut results = Vec::new();
+
+            for thread in threads {
+                results.push(thread.join().expect("steering thread panicked"));
+            }
+
+            results
+        };
+
+        self.index = Some(quad_tree);
+
+        let slowest_thread = results.iter().map(|(_, _, time)| *time).fold(0.0, f64::max);
+        let mean_thread = results.iter().map(|(_, _, time)| *time).sum::<f64>() / results.len() as f64;
+
+        self.neighbor_counts.clear();
+        let mut new_vels = Vec::with_capacity(results.len());
+
+        for (vels, neighbor_counts, _) in results {
+            new_vels.push(vels);
+            self.neighbor_counts.extend(neighbor_counts);
+        }
+
+        let leaders: Vec<Vec2> = self.boids.iter()
+            .filter(|boid| boid.is_leader)
+            .map(|boid| boid.location.clone())
+            .collect();
+        let leader_target = self.leader_target.clone().unwrap_or_else(|| self.leader_path());
+
+        let target_edge_factor = self.params.boundary_mode.edge_force_factor();
+        let transition = dt / BOUNDARY_TRANSITION;
+        self.edge_factor = target_edge_factor.clamp(self.edge_factor - transition, self.edge_factor + transition);
+
+        let mut index = 0;
+
+        let steer_time = start_time.elapsed().as_secs_f64() * 1000.0;
+        let start_time = Instant::now();
+
+        for (thread, mut vec) in new_vels.into_iter().enumerate() {
+            for boid_vel in &mut vec {
+                let boid = self.boids.get_mut(index).unwrap();
+                let previous_vel = boid.vel.clone();
+                boid.neighbor_count = self.neighbor_counts[index];
+                boid.thread = thread as u32;
+
+                index += 1;
+
+                if boid.pinned {
+                    continue;
+                }
+
+                // heavier boids react slower to their neighbors
+                boid.add_vel(boid_vel, 0.6 / boid.mass() * dt);
+
+                if let Some(goal) = &self.goal {
+                    let mut to_goal = goal.clone();
+                    to_goal.sub(&boid.location);
+                    to_goal.normalize();
+
+                    boid.add_vel(&mut to_goal, self.params.goal_weight * dt);
+                }
+
+                if boid.is_leader {
+                    let mut to_target = leader_target.clone();
+                    to_target.sub(&boid.location);
+                    to_target.normalize();
+
+                    boid.add_vel(&mut to_target, self.params.leader_target_weight * dt);
+                } else if let Some(mut to_leader) = nearest_offset(&boid.location, &leaders) {
+                    to_leader.normalize();
+
+                    boid.add_vel(&mut to_leader, self.params.leader_weight * dt);
+                }
+
+                boid.update(&self.params, dt, time, self.edge_factor, &mut self.rng);
+
+                // after the update so the jitter gets smoothed as well, per step so substeps smooth as much as a single update
+                if self.params.smoothing > 0.0 {
+                    boid.smooth_heading(&previous_vel, self.params.smoothing.powf(dt));
+                }
+            }
+        }
+
+        if self.params.resolve_collisions {
+            self.resolve_collisions();
+        }
+
+        StepTimes {
+            index: index_time,
+            steer: steer_time,
+            write_back: start_time.elapsed().as_secs_f64() * 1000.0,
+            slowest_thread,
+            mean_thread,
+        }
+    }
+
+    /// moves every pair of overlapping boids apart symmetrically until they just touch
+    ///
+    /// a single pass separates isolated pairs completely, in dense clusters pushing one pair apart can create new overlaps
+    pub fn resolve_collisions(&mut self) {
+        let quad_tree = self.build_index();
+        let max_scale = self.boids.iter().map(|boid| boid.scale).fold(0.0, f32::max);
+
+        let mut offsets = vec![Vec2::ZERO; self.boids.len()];
+        let mut neighbors = Vec::new();
+
+        for (index, boid) in self.boids.iter().enumerate() {
+            neighbors.clear();
+            in_circle_into(&quad_tree, (boid.location.x as f64, boid.location.y as f64), (SIZE * (boid.scale + max_scale)) as f64, &mut neighbors);
+
+            // every pair is handled once, by its lower index
+            for &other_index in neighbors.iter().filter(|other_index| **other_index > index) {
+                let other = &self.boids[other_index];
+                let min_distance = SIZE * (boid.scale + other.scale);
+
+                if boid.location.distance_squared(&other.location) >= min_distance * min_distance {
+                    continue;
+                }
+
+                let mut direction = boid.location.clone();
+                direction.sub(&other.location);
+                let distance = direction.length();
+
+                // boids on top of each other have no direction, any fixed one works
+                if distance == 0.0 {
+                    direction = Vec2::UNIT_X;
+                } else {
+                    direction.div(distance);
+                }
+
+                // a hair more than needed, so rounding can't leave them overlapping
+                direction.mul((min_distance - distance) / 2.0 + f32::EPSILON);
+
+                offsets[index].add(&direction);
+                offsets[other_index].sub(&direction);
+            }
+        }
+
+        for (boid, offset) in self.boids.iter_mut().zip(&offsets).filter(|(boid, _)| !boid.pinned) {
+            boid.location.add(offset);
+        }
+    }
+}
+
+/// the offset from `location` to the closest of `points`
+fn nearest_offset(location: &Vec2, points: &[Vec2]) -> Option<Vec2> {
+    points.iter()
+        .map(|point| {
+            let mut offset = point.clone();
+            offset.sub(location);
+            offset
+        })
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+}
+
+/// [steer_range] and the milliseconds it took
+fn timed_steer_range(boids: &[Boid], quad_tree: &QuadTree<usize>, params: &SimParams, behaviors: &[Arc<dyn SteeringBehavior>], range: Range<usize>) -> (Vec<Vec2>, Vec<u32>, f64) {
+    let start_time = Instant::now();
+    let (new_vel, neighbor_counts) = steer_range(boids, quad_tree, params, behaviors, range);
+
+    (new_vel, neighbor_counts, start_time.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// computes the steering of all boids in `range` and how many neighbors each of them has
+///
+/// only reads `boids`, so splitting them into ranges doesn't change the result
+fn steer_range(boids: &[Boid], quad_tree: &QuadTree<usize>, params: &SimParams, behaviors: &[Arc<dyn SteeringBehavior>], range: Range<usize>) -> (Vec<Vec2>, Vec<u32>) {
+    let mut new_vel = Vec::with_capacity(range.len());
+    let mut neighbor_counts = Vec::with_capacity(range.len());
+
+    // reused for every boid so the neighbor search doesn't allocate per boid
+    let mut neighbor_indices = Vec::new();
+    let mut neighbor_boids: Vec<&Boid> = Vec::new();
+
+    for index in range {
+        let boid = boids.get(index).unwrap();
+
+        let neighbor_count = find_neighbors(boids, quad_tree, params, index, &mut neighbor_indices);
+        neighbor_counts.push(neighbor_count as u32);
+
+        neighbor_boids.clear();
+        neighbor_boids.extend(neighbor_indices.iter().map(|neighbor_boid| boids.get(*neighbor_boid).unwrap()));
+
+        new_vel.push(compute_steering(boid, index, &neighbor_boids, &neighbor_indices, params, behaviors));
+    }
+
+    (new_vel, neighbor_counts)
+}
+
+/// fills `neighbor_indices` with the neighbors boid `index` steers by, without the boid itself
+///
+/// returns how many neighbors there were before [SimParams::max_neighbors] cut them down to the closest ones
+fn find_neighbors(boids: &[Boid], quad_tree: &QuadTree<usize>, params: &SimParams, index: usize, neighbor_indices: &mut Vec<usize>) -> usize {
+    let location = &boids[index].location;
+
+    neighbor_indices.clear();
+    in_circle_into(quad_tree, (location.x as f64, location.y as f64), params.perception_radius as f64, neighbor_indices);
+    neighbor_indices.retain(|neighbor_boid| *neighbor_boid != index);
+
+    let neighbor_count = neighbor_indices.len();
+
+    if let Some(max_neighbors) = params.max_neighbors {
+        if neighbor_count > max_neighbors {
+            neighbor_indices.select_nth_unstable_by(max_neighbors, |a, b| {
+                boids[*a].location.distance_squared(location).total_cmp(&boids[*b].location.distance_squared(location))
+            });
+            neighbor_indices.truncate(max_neighbors);
+        }
+    }
+
+    neighbor_count
+}
@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoidState {
+    pub location: (f32, f32),
+    pub vel: (f32, f32),
+    pub scale: f32,
+    pub is_leader: bool,
+    pub speed: f32,
+    pub pinned: bool,
+    pub age: u32,
+    pub color: [f32; 4],
+}
+
+/// the complete state of a [Simulation](crate::simulation::Simulation)
+///
+/// restoring it continues exactly like the original run as long as the parameters are the same,
+/// only an index reused with [Simulation::rebuild_interval](crate::simulation::Simulation::rebuild_interval) is rebuilt early
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub seed: u64,
+    pub frame: u64,
+    pub boids: Vec<BoidState>,
+    pub(crate) rng: ChaCha12Rng,
+    pub(crate) pending_births: f32,
+    /// part way between two boundary modes while switching
+    pub(crate) edge_factor: f32,
+    pub(crate) index_age: u32,
+    /// splits the boids between the threads in the next update
+    pub(crate) neighbor_counts: Vec<u32>,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> io::Result<Snapshot> {
+        let reader = BufReader::new(File::open(path)?);
+
+        bincode::deserialize_from(reader).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        bincode::serialize_into(writer, self).map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,283 @@
+use std::ops::Range;
+
+use spatial_neighbors::grid::Grid;
+use spatial_neighbors::quad_tree::{QuadTree, QuadTreeNode};
+use spatial_neighbors::SpatialPartitioner;
+
+/// single closest element queries on top of [SpatialPartitioner]
+pub trait Nearest<Data: Copy> {
+    /// the closest element to `position` which passes `filter` and its distance
+    fn nearest_where(&self, position: (f64, f64), filter: impl Fn(Data) -> bool) -> Option<(Data, f64)>;
+
+    fn nearest(&self, position: (f64, f64)) -> Option<(Data, f64)> {
+        self.nearest_where(position, |_| true)
+    }
+}
+
+fn distance_squared(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+impl<Data: Copy> Nearest<Data> for QuadTree<Data> {
+    fn nearest_where(&self, position: (f64, f64), filter: impl Fn(Data) -> bool) -> Option<(Data, f64)> {
+        let mut best = None;
+
+        nearest_in_node(self.node(), position, &filter, &mut best);
+
+        best.map(|(data, distance_squared)| (data, distance_squared.sqrt()))
+    }
+}
+
+/// like [SpatialPartitioner::in_circle] but appends to `data` instead of allocating a new `Vec`
+pub fn in_circle_into<Data: Copy>(quad_tree: &QuadTree<Data>, position: (f64, f64), radius: f64, data: &mut Vec<Data>) {
+    in_circle_in_node(quad_tree.node(), position, radius * radius, data);
+}
+
+fn in_circle_in_node<Data: Copy>(node: &QuadTreeNode<Data>, position: (f64, f64), radius_squared: f64, data: &mut Vec<Data>) {
+    if node_distance_squared(node, position) > radius_squared {
+        return;
+    }
+
+    // nodes completely inside the circle don't need any distance checks
+    if node_far_distance_squared(node, position) <= radius_squared {
+        push_all(node, data);
+        return;
+    }
+
+    for (element_position, element) in node.data() {
+        if distance_squared(*element_position, position) <= radius_squared {
+            data.push(*element);
+        }
+    }
+
+    if let Some(nodes) = node.nodes() {
+        for child in nodes.iter() {
+            in_circle_in_node(child, position, radius_squared, data);
+        }
+    }
+}
+
+/// appends every element within the rectangle from `min` to `max`, borders included
+pub fn in_rect_into<Data: Copy>(quad_tree: &QuadTree<Data>, min: (f64, f64), max: (f64, f64), data: &mut Vec<Data>) {
+    in_rect_in_node(quad_tree.node(), min, max, data);
+}
+
+fn in_rect_in_node<Data: Copy>(node: &QuadTreeNode<Data>, min: (f64, f64), max: (f64, f64), data: &mut Vec<Data>) {
+    let (center, size) = (node.center(), node.size());
+    let (node_min, node_max) = ((center.0 - size.0, center.1 - size.1), (center.0 + size.0, center.1 + size.1));
+
+    if node_max.0 < min.0 || node_min.0 > max.0 || node_max.1 < min.1 || node_min.1 > max.1 {
+        return;
+    }
+
+    // nodes completely inside the rectangle don't need any checks
+    if node_min.0 >= min.0 && node_max.0 <= max.0 && node_min.1 >= min.1 && node_max.1 <= max.1 {
+        push_all(node, data);
+        return;
+    }
+
+    for (position, element) in node.data() {
+        if (min.0..=max.0).contains(&position.0) && (min.1..=max.1).contains(&position.1) {
+            data.push(*element);
+        }
+    }
+
+    if let Some(nodes) = node.nodes() {
+        for child in nodes.iter() {
+            in_rect_in_node(child, min, max, data);
+        }
+    }
+}
+
+fn push_all<Data: Copy>(node: &QuadTreeNode<Data>, data: &mut Vec<Data>) {
+    data.extend(node.data().iter().map(|(_, element)| *element));
+
+    if let Some(nodes) = node.nodes() {
+        for child in nodes.iter() {
+            push_all(child, data);
+        }
+    }
+}
+
+/// squared distance from `position` to the farthest corner of `node`
+fn node_far_distance_squared<Data: Copy>(node: &QuadTreeNode<Data>, position: (f64, f64)) -> f64 {
+    let (center, size) = (node.center(), node.size());
+
+    let dx = (position.0 - center.0).abs() + size.0;
+    let dy = (position.1 - center.1).abs() + size.1;
+
+    dx * dx + dy * dy
+}
+
+/// squared distance from `position` to the area covered by `node`
+fn node_distance_squared<Data: Copy>(node: &QuadTreeNode<Data>, position: (f64, f64)) -> f64 {
+    let (center, size) = (node.center(), node.size());
+
+    let dx = ((position.0 - center.0).abs() - size.0).max(0.0);
+    let dy = ((position.1 - center.1).abs() - size.1).max(0.0);
+
+    dx * dx + dy * dy
+}
+
+fn nearest_in_node<Data: Copy>(node: &QuadTreeNode<Data>, position: (f64, f64), filter: &impl Fn(Data) -> bool, best: &mut Option<(Data, f64)>) {
+    for (element_position, data) in node.data() {
+        let distance = distance_squared(*element_position, position);
+
+        if best.is_none_or(|(_, best)| distance < best) && filter(*data) {
+            *best = Some((*data, distance));
+        }
+    }
+
+    let Some(nodes) = node.nodes() else {
+        return;
+    };
+
+    // visit the closest children first so the others can be skipped more often
+    let mut children: Vec<(f64, &QuadTreeNode<Data>)> = nodes.iter()
+        .map(|child| (node_distance_squared(child, position), child))
+        .collect();
+    children.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (distance, child) in children {
+        if best.is_some_and(|(_, best)| distance >= best) {
+            break;
+        }
+
+        nearest_in_node(child, position, filter, best);
+    }
+}
+
+/// [Grid] which remembers its area, needed to find the cell of a position for [Nearest]
+pub struct UniformGrid<Data: Copy> {
+    grid: Grid<Data>,
+    x: Range<f64>,
+    y: Range<f64>,
+}
+
+impl<Data: Copy> UniformGrid<Data> {
+    pub fn with_cell_count(x: Range<f64>, y: Range<f64>, cell_count: (usize, usize)) -> UniformGrid<Data> {
+        UniformGrid {
+            grid: Grid::with_cell_count(x.clone(), y.clone(), cell_count),
+            x,
+            y,
+        }
+    }
+
+    fn cell_of(&self, position: (f64, f64)) -> (i64, i64) {
+        let (cell_width, cell_height) = self.grid.cell_size();
+        let (count_x, count_y) = self.grid.cell_count();
+
+        let x = ((position.0 - self.x.start) / cell_width).floor() as i64;
+        let y = ((position.1 - self.y.start) / cell_height).floor() as i64;
+
+        (x.clamp(0, count_x as i64 - 1), y.clamp(0, count_y as i64 - 1))
+    }
+}
+
+impl<Data: Copy> SpatialPartitioner<Data> for UniformGrid<Data> {
+    fn new(x: Range<f64>, y: Range<f64>) -> Self {
+        UniformGrid {
+            grid: Grid::new(x.clone(), y.clone()),
+            x,
+            y,
+        }
+    }
+
+    fn insert(&mut self, position: (f64, f64), data: Data) {
+        self.grid.insert(position, data);
+    }
+
+    fn insert_unchecked(&mut self, position: (f64, f64), data: Data) {
+        self.grid.insert_unchecked(position, data);
+    }
+
+    fn count(&self) -> usize {
+        self.grid.count()
+    }
+
+    fn clear(&mut self) {
+        self.grid.clear();
+    }
+
+    fn in_circle(&self, position: (f64, f64), radius: f64) -> Vec<Data> {
+        self.grid.in_circle(position, radius)
+    }
+}
+
+impl<Data: Copy> Nearest<Data> for UniformGrid<Data> {
+    fn nearest_where(&self, position: (f64, f64), filter: impl Fn(Data) -> bool) -> Option<(Data, f64)> {
+        let (cell_width, cell_height) = self.grid.cell_size();
+        let (count_x, count_y) = self.grid.cell_count();
+        let (center_x, center_y) = self.cell_of(position);
+
+        let mut best: Option<(Data, f64)> = None;
+
+        // spiral outwards ring by ring until nothing outside of the rings searched so far can be closer
+        for ring in 0..(count_x.max(count_y) as i64) {
+            for x in (center_x - ring)..=(center_x + ring) {
+                for y in (center_y - ring)..=(center_y + ring) {
+                    let on_ring = (x - center_x).abs() == ring || (y - center_y).abs() == ring;
+
+                    if !on_ring || x < 0 || y < 0 || x >= count_x as i64 || y >= count_y as i64 {
+                        continue;
+                    }
+
+                    for (element_position, data) in self.grid.cell((x as usize, y as usize)) {
+                        let distance = distance_squared(*element_position, position);
+
+                        if best.is_none_or(|(_, best)| distance < best) && filter(*data) {
+                            best = Some((*data, distance));
+                        }
+                    }
+                }
+            }
+
+            // how close the cells beyond the searched square can get, measured from the real position rather than its
+            // cell as positions outside of the grid are clamped onto its border. sides at the edge of the grid have no cells left
+            let mut reach = f64::INFINITY;
+
+            if center_x - ring > 0 {
+                reach = reach.min(position.0 - (self.x.start + (center_x - ring) as f64 * cell_width));
+            }
+            if center_x + ring < count_x as i64 - 1 {
+                reach = reach.min(self.x.start + (center_x + ring + 1) as f64 * cell_width - position.0);
+            }
+            if center_y - ring > 0 {
+                reach = reach.min(position.1 - (self.y.start + (center_y - ring) as f64 * cell_height));
+            }
+            if center_y + ring < count_y as i64 - 1 {
+                reach = reach.min(self.y.start + (center_y + ring + 1) as f64 * cell_height - position.1);
+            }
+
+            let reach = reach.max(0.0);
+
+            if best.is_some_and(|(_, best)| best <= reach * reach) {
+                break;
+            }
+        }
+
+        best.map(|(data, distance_squared)| (data, distance_squared.sqrt()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_finds_the_nearest_for_positions_outside_of_it() {
+        let mut grid = UniformGrid::with_cell_count(0.0..10.0, 0.0..4.0, (5, 8));
+        let points = [(0.5, 3.5), (9.5, 0.2), (4.0, 2.0), (2.2, 0.1), (7.7, 3.9)];
+
+        for (index, point) in points.iter().enumerate() {
+            grid.insert(*point, index);
+        }
+
+        for position in [(-6.0, 3.8), (14.0, -3.0), (5.0, 12.0), (-0.1, -0.1), (4.0, 2.1)] {
+            let (_, distance) = grid.nearest(position).unwrap();
+            let expected = points.iter().map(|point| distance_squared(*point, position).sqrt()).fold(f64::INFINITY, f64::min);
+
+            assert!((distance - expected).abs() < 1e-9, "{:?}: {} instead of {}", position, distance, expected);
+        }
+    }
+}
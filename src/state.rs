@@ -1,41 +1,287 @@
-use std::ops::Sub;
-use std::sync::Arc;
-use std::thread;
-use std::time::Instant;
-
-use spatial_neighbors::quad_tree::QuadTree;
-use spatial_neighbors::SpatialPartitioner;
-use wgpu::{include_wgsl, RenderPipelineDescriptor, VertexState};
-use wgpu::util::{DeviceExt, StagingBelt};
-use wgpu_glyph::{ab_glyph, GlyphBrush, GlyphBrushBuilder, Section, Text};
-use winit::event::WindowEvent;
+use std::error::Error;
+use std::future::Future;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use instant::Instant;
+use wgpu::include_wgsl;
+use wgpu::util::StagingBelt;
+use wgpu_glyph::{ab_glyph, GlyphBrush, GlyphBrushBuilder, HorizontalAlign, Layout, Section, Text};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ModifiersState, MouseButton, VirtualKeyCode, WindowEvent};
+use winit::event_loop::EventLoopWindowTarget;
 use winit::window::Window;
 
-use crate::boid::Boid;
+use crate::args::{AdapterChoice, Args};
+use crate::boid::{MIN_VERTEX_COUNT, SIZE, SPRITE_SIZE};
+use crate::boid_csv;
+use crate::boundary::Boundary;
+use crate::camera::Camera;
+use crate::color::ColorMode;
+use crate::gui::Gui;
+use crate::input::InputEvent;
+use crate::mesh::Mesh;
+use crate::perf_log::{FrameTimes, PerfLog};
+use crate::readback::{capture_request, CaptureRequest, FrameReadback};
+use crate::pipeline::{create_blit_pipeline, create_render_pipeline};
+use crate::session::{Recorder, Replay, Session};
+use crate::brush::{Brush, BrushMode};
+use crate::shapes::{push_arrow, push_circle, push_gradient_strip, push_line, push_line_loop};
+use crate::sim_params::{AlignmentMode, BoundaryMode, JitterMode, SpawnPattern, MAX_PERCEPTION_RADIUS, MIN_PERCEPTION_RADIUS, PRESETS, Rule, SimParams};
+use crate::simulation::{Inspection, Simulation};
+use crate::snapshot::Snapshot;
+use crate::gpu_timer::GpuTimer;
+use crate::stats::{RunStats, TimingWindow};
+use crate::stream::FrameStream;
+use crate::texture::Texture;
+use crate::timeline::{Keyframe, Timeline};
+use crate::trails::{Trails, MAX_TRAIL_LENGTH};
 use crate::vec2::Vec2;
-use crate::vertex::Vertex;
+
+#[derive(Debug)]
+pub enum StateError {
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+    Sprite(image::ImageError),
+    Replay(io::Error),
+    PerfLog(io::Error),
+    Timeline(io::Error),
+    LoadBoids(io::Error),
+    Stream(io::Error),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::NoAdapter => write!(
+                f,
+                "no suitable graphics adapter was found, not even a software one. make sure a GPU driver supporting Vulkan, Metal or DX12 is installed, \
+                or without a GPU install a software renderer like lavapipe (mesa's Vulkan driver) or WARP, which then gets used as the fallback",
+            ),
+            StateError::RequestDevice(err) => write!(f, "the graphics adapter could not provide a device: {}", err),
+            StateError::Sprite(err) => write!(f, "the boid sprite could not be loaded: {}", err),
+            StateError::Replay(err) => write!(f, "the session to replay could not be loaded: {}", err),
+            StateError::PerfLog(err) => write!(f, "the performance log could not be created: {}", err),
+            StateError::Timeline(err) => write!(f, "the timeline could not be loaded: {}", err),
+            StateError::LoadBoids(err) => write!(f, "the boids could not be loaded: {}", err),
+            StateError::Stream(err) => write!(f, "the frame stream could not be started: {}", err),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// the flock moves on to the next waypoint once its centroid is this close to the current one
+const WAYPOINT_REACHED_DISTANCE: f32 = 0.1;
+
+const SNAPSHOT_PATH: &str = "snapshot.bin";
+/// the file name suggested for the printed parameters, nothing gets written to it
+const PARAMS_PATH: &str = "params.json";
+
+/// leaders spawned by toggling them on
+const LEADER_COUNT: usize = 3;
+const LEADER_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+const SELECTED_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 1.0];
+const PINNED_COLOR: [f32; 4] = [0.5, 1.0, 0.4, 1.0];
+
+/// strong enough to turn every boid but the ones right at the center radially
+const IMPULSE_STRENGTH: f32 = 20.0;
+
+/// the brush painted with the right mouse button
+const BRUSH_RADIUS: f32 = 0.2;
+/// turns boids at the center of the brush by about 17 degrees per update
+const BRUSH_STRENGTH: f32 = 0.3;
+const BRUSH_COLOR: [f32; 4] = [1.0, 0.5, 0.9, 0.6];
+
+const INSPECTED_NEIGHBOR_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 0.5];
+/// the steering arrows of an inspected boid, one per behavior and named in the overlay
+const STEERING_COLORS: [([f32; 4], &str); 4] = [
+    ([1.0, 0.3, 0.3, 1.0], "red"),
+    ([0.3, 1.0, 0.3, 1.0], "green"),
+    ([0.3, 0.5, 1.0, 1.0], "blue"),
+    ([1.0, 0.6, 0.1, 1.0], "orange"),
+];
+
+/// boids this close outside the view still get drawn, so partially visible ones don't pop at the edges
+const CULL_MARGIN: f32 = SPRITE_SIZE * 3.0;
+/// share of the visible width or height the arrow keys pan by
+const PAN_STEP: f32 = 0.1;
+/// distance of the hover tooltip from the cursor in logical pixels
+const TOOLTIP_OFFSET: f32 = 16.0;
+/// space around the flock when framing it
+const FRAME_MARGIN: f32 = 0.05;
+
+const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+const MAX_TIME_SCALE: f32 = 16.0;
+
+/// position and size of the color legend in logical pixels, measured from the bottom left corner
+const LEGEND_MARGIN: f32 = 10.0;
+const LEGEND_SIZE: (f32, f32) = (200.0, 12.0);
+
+/// used once aging gets turned on in the panel, about 10 seconds at 60 fps
+const DEFAULT_MAX_AGE: u32 = 600;
+
+/// what F7 steps through, below 1 the boids are drawn at a lower resolution and scaled up
+const RENDER_SCALES: [f32; 4] = [1.0, 0.75, 0.5, 0.25];
+
+/// how often acquiring the surface texture may time out before the frame is dropped
+const FRAME_ACQUIRE_ATTEMPTS: u32 = 2;
+/// the most updates run for one rendered frame at a fixed `--update-rate`
+const MAX_UPDATES_PER_FRAME: u32 = 8;
+
+/// with more boids only every nth one gets a velocity line, keeping the line mesh bounded
+const MAX_VELOCITY_LINES: usize = 5000;
+const MAX_VELOCITY_LINE_LENGTH: f32 = 0.1;
+/// range of the world half extents in the panel, larger worlds get zoomed out to fit the window
+const WORLD_BOUNDS_RANGE: (f32, f32) = (0.25, 10.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// anti-aliased circles computed in the fragment shader, 4 vertices per boid
+    Circles,
+    /// tessellated circles, see `vertex_count`
+    Shapes,
+    Sprites,
+    /// a single pixel per boid, meant for huge flocks
+    ///
+    /// points can't be resized in wgpu, so on HiDPI displays they are really small
+    Points,
+}
+
+impl RenderMode {
+    fn next(self) -> RenderMode {
+        match self {
+            RenderMode::Circles => RenderMode::Shapes,
+            RenderMode::Shapes => RenderMode::Sprites,
+            RenderMode::Sprites => RenderMode::Points,
+            RenderMode::Points => RenderMode::Circles,
+        }
+    }
+}
 
 pub struct State {
     surface: wgpu::Surface,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>,
     pub size: winit::dpi::PhysicalSize<u32>,
+    /// physical pixels per logical pixel, text and the legend are sized in logical pixels
+    scale_factor: f32,
 
     render_pipeline: wgpu::RenderPipeline,
-
-    boids: Vec<Boid>,
+    sprite_pipeline: wgpu::RenderPipeline,
+    circle_pipeline: wgpu::RenderPipeline,
+    point_pipeline: wgpu::RenderPipeline,
+    line_pipeline: wgpu::RenderPipeline,
+    sprite_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    blit_pipeline: wgpu::RenderPipeline,
+    render_scale: f32,
+    /// the boids get drawn into this at `render_scale` and scaled up, [None] draws them at full resolution
+    render_target: Option<(Texture, wgpu::BindGroup)>,
+    /// serves the rendered frames to a viewer, see `--stream`
+    stream: Option<FrameStream>,
+    /// captured frames get drawn here first, then read back and copied to the window. created on the first capture
+    capture_target: Option<(Texture, wgpu::BindGroup)>,
+    frame_readback: FrameReadback,
+    /// waiting for the next captured frame, see [State::capture_frame]
+    capture_requests: Vec<CaptureRequest>,
+    /// waiting for the frame currently being read back
+    pending_captures: Vec<CaptureRequest>,
+    /// the frame currently being read back goes to the stream as well
+    pending_stream_frame: bool,
+    camera: Camera,
+    render_mode: RenderMode,
+    color_mode: ColorMode,
+
+    /// the only flocking rule in effect together with the weights from before isolating it
+    isolated_rule: Option<(Rule, (f32, f32, f32))>,
+    /// index into [PRESETS] of the last applied preset
+    preset: Option<usize>,
+
+    paused: bool,
+    /// runs a single update even though the simulation is paused
+    step_once: bool,
+
+    simulation: Simulation,
+
+    vertex_count: u32,
+    auto_vertex_count: bool,
+
+    cursor: Option<PhysicalPosition<f64>>,
+    /// the boid nearest to the cursor when it last moved, shown in a tooltip
+    hovered: Option<usize>,
+    modifiers: ModifiersState,
+
+    /// the route of the flock, after the last waypoint it starts over at the first
+    waypoints: Vec<Vec2>,
+    current_waypoint: usize,
+    /// where the current drag selection started, in world coordinates
+    selection_start: Option<Vec2>,
+    /// indices of the boids in the last drag selection
+    selected: Vec<usize>,
+    /// the right mouse button is held, see [State::brush]
+    brushing: bool,
+    /// the boid shown with its neighbors and steering while the simulation is paused for it
+    inspection: Option<(usize, Inspection)>,
 
     staging_belt: StagingBelt,
-    glyph_brush: GlyphBrush<()>,
-
-    update_time: (u128, u128),
-    render_time: u128,
+    /// [None] if the font failed to load, no text is drawn then
+    glyph_brush: Option<GlyphBrush<()>>,
+    show_overlay: bool,
+    show_velocities: bool,
+    /// skips building geometry for boids outside the view, only matters when zoomed in
+    cull_offscreen: bool,
+    /// shown in the overlay to tell software or WebGL fallbacks apart in performance reports
+    adapter_info: wgpu::AdapterInfo,
+    /// the first error the device reported, the device can't be relied on afterwards
+    device_error: Arc<Mutex<Option<String>>>,
+    show_adapter_info: bool,
+    velocity_line_length: f32,
+    /// used whenever trails get turned on, the simulation only records them while they are shown
+    trail_length: usize,
+    max_vertices: usize,
+    /// the fallback to points is only logged once
+    vertex_cap_warned: bool,
+    gui: Gui,
+
+    render_time: TimingWindow,
+    /// [None] if the adapter doesn't support timestamp queries
+    gpu_timer: Option<GpuTimer>,
+    gpu_render_time: TimingWindow,
+
+    frame: u64,
+    nearest_distance: f32,
+
+    pub stats: RunStats,
+    max_frames: Option<u64>,
+
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+
+    perf_log: Option<PerfLog>,
+    /// overrides the parameters every update, changes from the panel or keys don't last
+    timeline: Option<Timeline>,
+    /// whether an update ran since the last render, only then the perf log gets update timings
+    updated: bool,
+
+    /// simulation updates per second, [None] runs one update per rendered frame
+    update_rate: Option<f32>,
+    /// time not yet simulated at `update_rate`, less than one update after [State::update]
+    update_lag: Duration,
+    last_update: Option<Instant>,
+    /// boid locations before the last update, rendering blends from them towards the current ones by the `update_lag`
+    previous_locations: Vec<Vec2>,
 }
 
 impl State {
     // Creating some of the wgpu types requires async code
-    pub async fn new(window: &Window) -> Self {
+    pub async fn new<T>(window: &Window, event_loop: &EventLoopWindowTarget<T>, args: &Args) -> Result<Self, StateError> {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -44,17 +290,15 @@ impl State {
 
         let surface = unsafe { instance.create_surface(window) };
 
-        let adapter = instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            },
-        ).await.unwrap();
+        let adapter = request_adapter(&instance, &surface, args.adapter).await.ok_or(StateError::NoAdapter)?;
+
+        let adapter_info = adapter.get_info();
+        log::info!("using {} ({:?}, {:?}), driver: {} {}", adapter_info.name, adapter_info.backend, adapter_info.device_type, adapter_info.driver, adapter_info.driver_info);
 
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
+                // only used for measuring GPU time, which just shows N/A without it
+                features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                 // WebGL doesn't support all of wgpu's features, so if
                 // we're building for the web we'll have to disable some.
                 limits: if cfg!(target_arch = "wasm32") {
@@ -65,98 +309,247 @@ impl State {
                 label: None,
             },
             None, // Trace path
-        ).await.unwrap();
+        ).await.map_err(StateError::RequestDevice)?;
+
+        // wgpu panics on errors by default, a lost device (driver reset, GPU switch) would take the whole process down
+        let device_error = Arc::new(Mutex::new(None));
+        let handler_error = device_error.clone();
+        device.on_uncaptured_error(move |err| {
+            log::error!("graphics device error: {}", err);
+
+            if let Ok(mut device_error) = handler_error.lock() {
+                device_error.get_or_insert(err.to_string());
+            }
+        });
+
+        // fifo is the only mode every surface has to support
+        let present_modes = surface.get_supported_present_modes(&adapter);
+        let present_mode = if present_modes.contains(&args.present_mode) {
+            args.present_mode
+        } else {
+            eprintln!("present mode {:?} is not supported, falling back to Fifo", args.present_mode);
+            wgpu::PresentMode::Fifo
+        };
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: wgpu::TextureFormat::Bgra8UnormSrgb,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode,
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
         };
         surface.configure(&device, &config);
 
         let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
 
+        let replay = match &args.replay {
+            Some(path) => Some(Replay::new(Session::load(path).map_err(StateError::Replay)?)),
+            None => None,
+        };
+
+        // a replay keeps the recorded world whatever size the window has
+        let fixed_world_bounds = match &replay {
+            Some(replay) => replay.world_bounds().or_else(|| args.world_bounds.clone()),
+            None => args.world_bounds.clone(),
+        };
+
+        let camera = Camera::new(&device, size, args.letterbox, fixed_world_bounds);
+
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[],
+                bind_group_layouts: &[&camera.bind_group_layout],
                 push_constant_ranges: &[],
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main", // 1.
-                buffers: &[Vertex::desc()], // 2.
-            },
-            fragment: Some(wgpu::FragmentState { // 3.
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState { // 4.
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw, // 2.
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None, // 1.
-            multisample: wgpu::MultisampleState {
-                count: 1, // 2.
-                mask: !0, // 3.
-                alpha_to_coverage_enabled: false, // 4.
-            },
-            multiview: None, // 5.
-        });
+        let render_pipeline = create_render_pipeline(&device, "Render Pipeline", &render_pipeline_layout, &shader, "fs_main", config.format, wgpu::PrimitiveTopology::TriangleList);
+
+        let circle_pipeline = create_render_pipeline(&device, "Circle Pipeline", &render_pipeline_layout, &shader, "fs_circle", config.format, wgpu::PrimitiveTopology::TriangleList);
+
+        let point_pipeline = create_render_pipeline(&device, "Point Pipeline", &render_pipeline_layout, &shader, "fs_main", config.format, wgpu::PrimitiveTopology::PointList);
+
+        let line_pipeline = create_render_pipeline(&device, "Line Pipeline", &render_pipeline_layout, &shader, "fs_main", config.format, wgpu::PrimitiveTopology::LineList);
+
+        let sprite = match &args.sprite {
+            Some(path) => image::open(path).map(|image| Texture::from_image(&device, &queue, &image, "Sprite Texture")),
+            None => Texture::from_bytes(&device, &queue, include_bytes!("boid.png"), "Sprite Texture"),
+        }.map_err(StateError::Sprite)?;
+
+        let texture_bind_group_layout = Texture::bind_group_layout(&device);
+        let sprite_bind_group = sprite.bind_group(&device, &texture_bind_group_layout);
+
+        let sprite_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sprite Pipeline Layout"),
+                bind_group_layouts: &[&camera.bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let sprite_pipeline = create_render_pipeline(&device, "Sprite Pipeline", &sprite_pipeline_layout, &shader, "fs_sprite", config.format, wgpu::PrimitiveTopology::TriangleList);
+
+        let blit_pipeline = create_blit_pipeline(&device, &sprite_pipeline_layout, &shader, config.format);
+        let render_target = create_render_target(&device, &texture_bind_group_layout, size, args.render_scale, config.format);
+
+        let stream = match &args.stream {
+            Some(_) if !FrameReadback::supports(config.format) => {
+                return Err(StateError::Stream(io::Error::other(format!("frames in {:?} can't be read back", config.format))));
+            }
+            Some(address) => Some(FrameStream::bind(address, args.stream_fps).map_err(StateError::Stream)?),
+            None => None,
+        };
+
+        let mut gui = Gui::new(event_loop, window, &device, config.format);
 
         // Create staging belt
         let staging_belt = StagingBelt::new(1024);
 
         // Prepare glyph_brush
-        let inconsolata = ab_glyph::FontArc::try_from_slice(include_bytes!(
-            "Inconsolata-Regular.ttf"
-        )).unwrap();
+        let glyph_brush = match ab_glyph::FontArc::try_from_slice(include_bytes!("Inconsolata-Regular.ttf")) {
+            Ok(inconsolata) => Some(GlyphBrushBuilder::using_font(inconsolata).build(&device, wgpu::TextureFormat::Bgra8UnormSrgb)),
+            Err(err) => {
+                log::warn!("the overlay font could not be loaded, running without text: {}", err);
+                None
+            }
+        };
 
-        let glyph_brush = GlyphBrushBuilder::using_font(inconsolata)
-            .build(&device, wgpu::TextureFormat::Bgra8UnormSrgb);
+        let params = SimParams {
+            perception_radius: args.perception_radius.unwrap_or(SimParams::default().perception_radius),
+            max_neighbors: args.max_neighbors,
+            spawn_speed: args.spawn_speed,
+            spawn_pattern: args.spawn_pattern,
+            spawn_heading: args.spawn_heading,
+            spawn_heading_spread: args.spawn_heading_spread,
+            world_bounds: camera.world_bounds().clone(),
+            edge_margin: args.edge_margin.unwrap_or(SimParams::default().edge_margin),
+            boundary: args.boundary.clone().map(|polygon| Arc::new(polygon) as Arc<dyn Boundary>),
+            ..SimParams::default()
+        };
+
+        let seed = match &replay {
+            Some(replay) => replay.seed(),
+            None => args.seed.unwrap_or_else(rand::random),
+        };
+
+        let update_rate = match &replay {
+            Some(replay) => replay.update_rate(),
+            None => args.update_rate,
+        };
+
+        let recorder = args.record_session.clone().map(|path| Recorder::new(path, seed, camera.world_bounds(), update_rate));
+
+        // only input events get recorded, changes made in the panel would be missing from the replay
+        gui.visible = recorder.is_none() && replay.is_none();
+
+        let timeline = match &args.timeline {
+            Some(path) => Some(Timeline::load(path).map_err(StateError::Timeline)?),
+            None => None,
+        };
+
+        let perf_log = match &args.perf_log {
+            Some(path) => Some(PerfLog::create(path).map_err(StateError::PerfLog)?),
+            None => None,
+        };
+
+        log::info!("boids: {}, seed: {}, radius: {}, threads: {}, max neighbors: {:?}", args.boid_count, seed, params.perception_radius, args.threads, params.max_neighbors);
 
-        let mut boids = Vec::new();
+        let mut simulation = Simulation::new(args.boid_count, params, seed);
+        simulation.thread_count = args.threads;
+        simulation.parallel_threshold = args.parallel_threshold;
+        simulation.index_capacity = args.quadtree_capacity;
 
-        for _ in 0..10000 {
-            boids.push(Boid::new_random());
+        if let Some(path) = &args.load_boids {
+            simulation.set_boids(boid_csv::load(path, camera.world_bounds(), simulation.params.max_speed).map_err(StateError::LoadBoids)?);
         }
 
-        Self {
+        let gpu_timer = GpuTimer::new(&device, &queue);
+
+        Ok(Self {
             surface,
             device,
             queue,
             config,
+            present_modes,
             size,
+            scale_factor: window.scale_factor() as f32,
             render_pipeline,
-
-            boids,
+            sprite_pipeline,
+            circle_pipeline,
+            point_pipeline,
+            line_pipeline,
+            sprite_bind_group,
+            texture_bind_group_layout,
+            blit_pipeline,
+            render_scale: args.render_scale,
+            render_target,
+            stream,
+            capture_target: None,
+            frame_readback: FrameReadback::default(),
+            capture_requests: Vec::new(),
+            pending_captures: Vec::new(),
+            pending_stream_frame: false,
+            camera,
+            render_mode: if args.sprite.is_some() { RenderMode::Sprites } else { RenderMode::Circles },
+            color_mode: ColorMode::Plain,
+
+            isolated_rule: None,
+            preset: None,
+
+            paused: false,
+            step_once: false,
+
+            simulation,
+
+            vertex_count: 8,
+            auto_vertex_count: true,
+
+            cursor: None,
+            hovered: None,
+            modifiers: ModifiersState::empty(),
+
+            waypoints: Vec::new(),
+            current_waypoint: 0,
+            selection_start: None,
+            brushing: false,
+            inspection: None,
+            selected: Vec::new(),
 
             staging_belt,
             glyph_brush,
-
-            update_time: (0, 0),
-            render_time: 0,
-        }
+            show_overlay: true,
+            show_velocities: false,
+            cull_offscreen: true,
+            adapter_info,
+            device_error,
+            show_adapter_info: false,
+            velocity_line_length: 0.02,
+            trail_length: args.trail_length,
+            max_vertices: args.max_vertices,
+            vertex_cap_warned: false,
+            gui,
+
+            render_time: TimingWindow::default(),
+            gpu_timer,
+            gpu_render_time: TimingWindow::default(),
+
+            frame: 0,
+            nearest_distance: 0.0,
+
+            stats: RunStats::default(),
+            max_frames: args.max_frames,
+
+            recorder,
+            replay,
+
+            perf_log,
+            timeline,
+            updated: false,
+
+            update_rate,
+            update_lag: Duration::ZERO,
+            last_update: None,
+            previous_locations: Vec::new(),
+        })
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -165,121 +558,626 @@ impl State {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+
+            self.camera.resize(&self.queue, new_size);
+            self.apply_world_bounds();
+
+            self.render_target = create_render_target(&self.device, &self.texture_bind_group_layout, new_size, self.render_scale, self.config.format);
+            // recreated at the new size by the next capture
+            self.capture_target = None;
         }
     }
 
-    pub fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    /// fixes the world at `world_bounds` across resizes, [None] makes it follow the window again
+    fn set_world_bounds(&mut self, world_bounds: Option<Vec2>) {
+        self.camera.set_world_bounds(&self.queue, self.size, world_bounds);
+        self.apply_world_bounds();
     }
 
-    pub fn update(&mut self) {
-        let start_time = Instant::now();
+    /// hands the camera's world to the simulation, recording it if it changed
+    fn apply_world_bounds(&mut self) {
+        let world_bounds = self.camera.world_bounds().clone();
+
+        if let Some(recorder) = &mut self.recorder {
+            if world_bounds != self.simulation.params.world_bounds {
+                recorder.record(self.frame, &InputEvent::WorldResized(world_bounds.x, world_bounds.y));
+            }
+        }
+
+        self.simulation.set_world_bounds(world_bounds);
+    }
+
+    /// steps down through [RENDER_SCALES], starting over at full resolution
+    fn cycle_render_scale(&mut self) {
+        self.render_scale = RENDER_SCALES.iter().copied().find(|scale| *scale < self.render_scale).unwrap_or(1.0);
+        self.render_target = create_render_target(&self.device, &self.texture_bind_group_layout, self.size, self.render_scale, self.config.format);
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor as f32;
+    }
+
+    /// moves the view by a share of its visible size
+    fn pan(&mut self, x: f32, y: f32) {
+        let (min, max) = self.camera.visible_bounds();
+        let center = self.camera.center();
+        let center = Vec2::new(center.x + (max.x - min.x) * x, center.y + (max.y - min.y) * y);
+
+        self.camera.set_center(&self.queue, center);
+    }
+
+    fn toggle_trails(&mut self) {
+        self.simulation.trails = match self.simulation.trails {
+            Some(_) => None,
+            None => Some(Trails::new(self.trail_length)),
+        };
+    }
+
+    /// switches to the next present mode supported by the surface
+    fn cycle_present_mode(&mut self) {
+        let current = self.present_modes.iter().position(|mode| *mode == self.config.present_mode).unwrap_or(0);
 
-        const RADIUS: f64 = 0.03;
+        self.config.present_mode = self.present_modes[(current + 1) % self.present_modes.len()];
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    /// respawns all boids and forgets the timings measured so far
+    pub fn reset(&mut self) {
+        self.simulation.reset();
+
+        self.current_waypoint = 0;
+        self.render_time.clear();
+        self.gpu_render_time.clear();
+    }
 
-        let mut quad_tree = QuadTree::with_capacity(-1.1..1.1, -1.1..1.1, 75);
+    /// forgets the timings measured so far, e.g. to get rid of a hitch
+    pub fn reset_timings(&mut self) {
+        self.simulation.reset_timings();
+        self.render_time.clear();
+        self.gpu_render_time.clear();
+    }
 
-        for (index, boid) in self.boids.iter().enumerate() {
-            quad_tree.insert((boid.location.x as f64, boid.location.y as f64), index);
+    /// the vertex count actually used for rendering
+    ///
+    /// when `auto_vertex_count` is set it gets reduced so that an edge is roughly 2 pixels long
+    fn effective_vertex_count(&self) -> u32 {
+        if !self.auto_vertex_count {
+            return self.vertex_count;
         }
 
-        let quad_tree = Arc::new(quad_tree);
-        let boids = Arc::new(self.boids.clone());
+        let radius = SIZE * self.camera.pixels_per_unit(self.size);
+        let vertex_count = (std::f32::consts::PI * 2.0 * radius / 2.0).ceil() as u32;
 
-        let thread_count = num_cpus::get();
+        vertex_count.clamp(MIN_VERTEX_COUNT, self.vertex_count)
+    }
 
-        let mut threads = Vec::new();
+    /// stretches `target` over the whole render pass
+    fn draw_blit<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, target: &'a wgpu::BindGroup) {
+        // the blit pipeline shares the sprite layout, which needs the camera as well
+        render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+        render_pass.set_bind_group(1, target, &[]);
+        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.draw(0..3, 0..1);
+    }
 
-        let boid_count = self.boids.len();
-        let boids_per_thread = boid_count as f32 / thread_count as f32;
+    /// limits drawing in a target of `size` to the part the world covers, the letterbox bars only get cleared
+    fn set_viewport(&self, render_pass: &mut wgpu::RenderPass, size: winit::dpi::PhysicalSize<u32>) {
+        let [x, y, width, height] = self.camera.viewport(size);
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+    }
 
-        for i in 0..thread_count {
-            let range = (boids_per_thread * i as f32).ceil() as usize..((boids_per_thread * (i + 1) as f32).ceil() as usize);
+    /// binds the pipeline of `render_mode` and draws `boid_mesh` with it
+    fn draw_boids<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, render_mode: RenderMode, boid_mesh: &'a Mesh) {
+        render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
 
-            let boids = boids.clone();
-            let quad_tree = quad_tree.clone();
+        match render_mode {
+            RenderMode::Circles => render_pass.set_pipeline(&self.circle_pipeline),
+            RenderMode::Shapes => render_pass.set_pipeline(&self.render_pipeline),
+            RenderMode::Sprites => {
+                render_pass.set_pipeline(&self.sprite_pipeline);
+                render_pass.set_bind_group(1, &self.sprite_bind_group, &[]);
+            }
+            RenderMode::Points => render_pass.set_pipeline(&self.point_pipeline),
+        }
 
-            threads.push(thread::spawn(move || {
-                let mut new_vel = Vec::with_capacity(boids.len());
+        boid_mesh.draw(render_pass);
+    }
 
-                for index in range {
-                    let boid = boids.get(index).unwrap();
-                    let neighbor_boids = quad_tree.in_circle((boid.location.x as f64, boid.location.y as f64), RADIUS);
+    /// the render mode and vertex count actually used, keeping the boid mesh within `max_vertices`
+    ///
+    /// shapes get coarser first, anything still too large falls back to points
+    fn capped_render_mode(&mut self, vertex_count: u32) -> (RenderMode, u32) {
+        let boid_count = self.simulation.boids().len().max(1);
+        let vertices_per_boid = match self.render_mode {
+            RenderMode::Circles | RenderMode::Sprites => 4,
+            RenderMode::Shapes => vertex_count as usize,
+            RenderMode::Points => 1,
+        };
 
-                    let mut separation = Vec2::new(0.0, 0.0);
-                    let mut alignment = Vec2::new(0.0, 0.0);
-                    let mut cohesion = Vec2::new(0.0, 0.0);
+        if boid_count * vertices_per_boid <= self.max_vertices {
+            return (self.render_mode, vertex_count);
+        }
 
-                    for neighbor_boid in &neighbor_boids {
-                        if index == *neighbor_boid {
-                            continue;
-                        }
+        let fitting_vertex_count = (self.max_vertices / boid_count) as u32;
 
-                        let neighbor_boid = boids.get(*neighbor_boid).unwrap();
+        if self.render_mode == RenderMode::Shapes && fitting_vertex_count >= MIN_VERTEX_COUNT {
+            return (RenderMode::Shapes, fitting_vertex_count);
+        }
 
-                        let mut separation_vec = boid.location.clone();
-                        separation_vec.sub(&neighbor_boid.location);
+        if !self.vertex_cap_warned {
+            log::warn!("{} boids take more than {} vertices in {:?} mode, drawing them as points instead", boid_count, self.max_vertices, self.render_mode);
+            self.vertex_cap_warned = true;
+        }
 
-                        let new_length = ((RADIUS as f32 - separation_vec.length()) / RADIUS as f32).powi(3);
+        (RenderMode::Points, vertex_count)
+    }
 
-                        separation_vec.normalize();
-                        separation_vec.mul(new_length);
+    /// the cursor position in world coordinates
+    fn cursor_world(&self) -> Option<Vec2> {
+        let cursor = self.cursor?;
 
-                        separation.add(&separation_vec);
-                        alignment.add(&neighbor_boid.vel);
+        Some(self.camera.screen_to_world((cursor.x, cursor.y), self.size))
+    }
 
-                        cohesion.add(&neighbor_boid.location);
-                    }
+    /// the force painted under the cursor while the right mouse button is held.
+    /// it attracts, with shift it repels and with ctrl it swirls
+    fn brush(&self) -> Option<Brush> {
+        if !self.brushing {
+            return None;
+        }
+
+        let mode = if self.modifiers.shift() {
+            BrushMode::Repel
+        } else if self.modifiers.ctrl() {
+            BrushMode::Swirl
+        } else {
+            BrushMode::Attract
+        };
+
+        Some(Brush {
+            center: self.cursor_world()?,
+            radius: BRUSH_RADIUS,
+            strength: BRUSH_STRENGTH,
+            mode,
+        })
+    }
+
+    pub fn boid_count(&self) -> usize {
+        self.simulation.boids().len()
+    }
+
+    /// the first error reported by the graphics device, e.g. because it was lost
+    pub fn device_error(&self) -> Option<String> {
+        self.device_error.lock().ok()?.clone()
+    }
+
+    /// whether `--max-frames` frames have been rendered
+    pub fn frame_limit_reached(&self) -> bool {
+        self.max_frames.is_some_and(|max_frames| self.stats.frames() >= max_frames)
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        // a replay controls everything on its own
+        if self.replay.is_some() {
+            return false;
+        }
+
+        if self.gui.on_event(event) {
+            return true;
+        }
+
+        let Some(event) = InputEvent::from_window_event(event) else {
+            return false;
+        };
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(self.frame, &event);
+        }
 
-                    separation.div(neighbor_boids.len() as f32);
-                    separation.mul(2.0);
+        self.handle_input(&event)
+    }
 
-                    alignment.div(neighbor_boids.len() as f32);
-                    alignment.mul(0.5);
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::KeyPressed(key) => self.key_pressed(*key),
+            InputEvent::MouseWheel(lines) if self.modifiers.ctrl() => {
+                let anchor = self.cursor_world().unwrap_or_else(|| self.camera.center().clone());
+                self.camera.zoom_around(&self.queue, 1.1_f32.powf(*lines), &anchor);
+                true
+            }
+            InputEvent::MouseWheel(lines) => {
+                self.simulation.params.perception_radius = (self.simulation.params.perception_radius * 1.1_f32.powf(*lines)).clamp(MIN_PERCEPTION_RADIUS, MAX_PERCEPTION_RADIUS);
+                true
+            }
+            InputEvent::CursorMoved(x, y) => {
+                self.cursor = Some(PhysicalPosition::new(*x, *y));
+                self.hovered = self.cursor_world().and_then(|cursor| self.simulation.nearest_boid(&cursor));
+                false
+            }
+            InputEvent::CursorLeft => {
+                self.cursor = None;
+                self.hovered = None;
+                false
+            }
+            InputEvent::MousePressed(MouseButton::Left) if self.modifiers.shift() => {
+                match self.cursor_world() {
+                    Some(cursor) => {
+                        self.waypoints.push(cursor);
+                        true
+                    }
+                    None => false
+                }
+            }
+            InputEvent::MousePressed(MouseButton::Left) if self.modifiers.ctrl() => match self.hovered {
+                Some(index) => {
+                    self.simulation.toggle_pinned(index);
+                    true
+                }
+                None => false,
+            },
+            InputEvent::MousePressed(MouseButton::Left) => {
+                self.selection_start = self.cursor_world();
+                self.selection_start.is_some()
+            }
+            InputEvent::MousePressed(MouseButton::Right) => {
+                self.brushing = true;
+                true
+            }
+            InputEvent::MousePressed(_) => false,
+            InputEvent::MouseReleased(MouseButton::Left) => match (self.selection_start.take(), self.cursor_world()) {
+                (Some(start), Some(end)) => {
+                    let (min, max) = selection_rect(&start, &end);
+                    self.selected = self.simulation.boids_in_rect(&min, &max);
+                    true
+                }
+                _ => false,
+            },
+            InputEvent::MouseReleased(MouseButton::Right) => {
+                self.brushing = false;
+                true
+            }
+            InputEvent::MouseReleased(_) => false,
+            InputEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                false
+            }
+            InputEvent::WorldResized(x, y) => {
+                self.set_world_bounds(Some(Vec2::new(*x, *y)));
+                true
+            }
+        }
+    }
 
-                    cohesion.div(neighbor_boids.len() as f32);
-                    cohesion.sub(&boid.location);
-                    cohesion.mul(0.6);
+    /// writes the recorded session, if there is one
+    pub fn save_recording(&self) -> io::Result<()> {
+        match &self.recorder {
+            Some(recorder) => recorder.save(),
+            None => Ok(()),
+        }
+    }
 
-                    cohesion.add(&separation);
-                    cohesion.add(&alignment);
+    pub fn flush_perf_log(&mut self) -> io::Result<()> {
+        match &mut self.perf_log {
+            Some(perf_log) => perf_log.flush(),
+            None => Ok(()),
+        }
+    }
 
-                    new_vel.push(cohesion);
+    fn key_pressed(&mut self, key: VirtualKeyCode) -> bool {
+        match key {
+            VirtualKeyCode::R => self.reset(),
+            VirtualKeyCode::Left => self.pan(-PAN_STEP, 0.0),
+            VirtualKeyCode::Right => self.pan(PAN_STEP, 0.0),
+            VirtualKeyCode::Up => self.pan(0.0, PAN_STEP),
+            VirtualKeyCode::Down => self.pan(0.0, -PAN_STEP),
+            VirtualKeyCode::Home => self.camera.reset_view(&self.queue),
+            VirtualKeyCode::Z => {
+                if let Some(centroid) = self.simulation.centroid() {
+                    self.camera.set_center(&self.queue, centroid);
                 }
+            }
+            VirtualKeyCode::X => {
+                if let Some((min, max, _)) = self.simulation.bounds_and_centroid() {
+                    let min = Vec2::new(min.x - FRAME_MARGIN, min.y - FRAME_MARGIN);
+                    let max = Vec2::new(max.x + FRAME_MARGIN, max.y + FRAME_MARGIN);
+
+                    self.camera.fit(&self.queue, &min, &max);
+                }
+            }
+            VirtualKeyCode::T => self.reset_timings(),
+            VirtualKeyCode::N => println!("{}", self.simulation.neighbor_histogram()),
+            VirtualKeyCode::LBracket => self.vertex_count = (self.vertex_count - 1).max(MIN_VERTEX_COUNT),
+            VirtualKeyCode::RBracket => self.vertex_count += 1,
+            VirtualKeyCode::M => self.render_mode = self.render_mode.next(),
+            VirtualKeyCode::C => self.color_mode = self.color_mode.next(),
+            VirtualKeyCode::J => self.toggle_thread_colors(),
+            VirtualKeyCode::Space => self.paused = !self.paused,
+            VirtualKeyCode::Period => self.step_once = self.paused,
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => self.simulation.time_scale = (self.simulation.time_scale / 2.0).max(MIN_TIME_SCALE),
+            VirtualKeyCode::Equals | VirtualKeyCode::NumpadAdd => self.simulation.time_scale = (self.simulation.time_scale * 2.0).min(MAX_TIME_SCALE),
+            VirtualKeyCode::Backslash => self.auto_vertex_count = !self.auto_vertex_count,
+            VirtualKeyCode::G => self.gui.visible = !self.gui.visible && self.recorder.is_none() && self.replay.is_none(),
+            VirtualKeyCode::H => self.show_overlay = !self.show_overlay,
+            VirtualKeyCode::E => self.simulation.apply_radial_impulse(&Vec2::ZERO, IMPULSE_STRENGTH),
+            VirtualKeyCode::Q => self.simulation.apply_radial_impulse(&Vec2::ZERO, -IMPULSE_STRENGTH),
+            VirtualKeyCode::V => self.show_velocities = !self.show_velocities,
+            VirtualKeyCode::P => self.toggle_trails(),
+            VirtualKeyCode::A => self.show_adapter_info = !self.show_adapter_info,
+            VirtualKeyCode::F5 => match self.simulation.snapshot().save(Path::new(SNAPSHOT_PATH)) {
+                Ok(()) => println!("saved snapshot to {}", SNAPSHOT_PATH),
+                Err(err) => eprintln!("failed to save snapshot: {}", err),
+            },
+            VirtualKeyCode::F9 => match Snapshot::load(Path::new(SNAPSHOT_PATH)) {
+                Ok(snapshot) => self.simulation.restore(&snapshot),
+                Err(err) => eprintln!("failed to load snapshot: {}", err),
+            },
+            VirtualKeyCode::F6 => self.cycle_present_mode(),
+            VirtualKeyCode::F7 => self.cycle_render_scale(),
+            VirtualKeyCode::L => {
+                let count = if self.simulation.leader_count() == 0 { LEADER_COUNT } else { 0 };
+                self.simulation.set_leader_count(count);
+            }
+            VirtualKeyCode::I => self.simulation.freeze_index = !self.simulation.freeze_index,
+            VirtualKeyCode::K => self.simulation.params.resolve_collisions = !self.simulation.params.resolve_collisions,
+            VirtualKeyCode::F1 => self.isolate_rule(Rule::Separation),
+            VirtualKeyCode::F2 => self.isolate_rule(Rule::Alignment),
+            VirtualKeyCode::F3 => self.isolate_rule(Rule::Cohesion),
+            VirtualKeyCode::F4 => {
+                if let Some((_, weights)) = self.isolated_rule.take() {
+                    self.simulation.params.set_weights(weights);
+                }
+            }
+            VirtualKeyCode::B => self.next_preset(),
+            VirtualKeyCode::O => self.toggle_inspection(),
+            VirtualKeyCode::D => self.print_params(),
+            VirtualKeyCode::W => self.simulation.params.boundary_mode = self.simulation.params.boundary_mode.next(),
+            VirtualKeyCode::Delete => {
+                self.waypoints.clear();
+                self.current_waypoint = 0;
+            }
+            _ => return false
+        }
+
+        true
+    }
+
+    /// turns off all flocking rules but `rule` until F4 restores the previous weights
+    fn isolate_rule(&mut self, rule: Rule) {
+        let weights = match self.isolated_rule {
+            Some((_, weights)) => weights,
+            None => self.simulation.params.weights(),
+        };
+
+        self.simulation.params.isolate(rule, weights);
+        self.isolated_rule = Some((rule, weights));
+    }
+
+    /// pauses to show how the boid nearest to the cursor steers, or resumes if a boid is already inspected
+    fn toggle_inspection(&mut self) {
+        if self.inspection.take().is_some() {
+            self.paused = false;
+            return;
+        }
+
+        if let Some(index) = self.hovered {
+            self.inspection = self.simulation.inspect(index).map(|inspection| (index, inspection));
+            self.paused = true;
+        }
+    }
+
+    /// switches between coloring by steering thread and plain colors
+    fn toggle_thread_colors(&mut self) {
+        self.color_mode = match self.color_mode {
+            ColorMode::Threads => ColorMode::Plain,
+            _ => ColorMode::Threads,
+        };
+    }
+
+    /// applies the preset after the last applied one, replacing any isolated rule
+    fn next_preset(&mut self) {
+        let index = self.preset.map_or(0, |index| (index + 1) % PRESETS.len());
+
+        PRESETS[index].apply(&mut self.simulation.params);
+        self.isolated_rule = None;
+        self.preset = Some(index);
+    }
+
+    /// prints the flags and a single keyframe timeline that start the simulation with the current setup again
+    ///
+    /// the keyframe holds every parameter, so the timeline keeps them fixed for the whole run
+    fn print_params(&self) {
+        let keyframe = Keyframe {
+            frame: 0,
+            params: self.simulation.params.clone(),
+        };
+
+        let timeline = match serde_json::to_string_pretty(&[keyframe]) {
+            Ok(timeline) => timeline,
+            Err(err) => {
+                eprintln!("failed to serialize the parameters: {}", err);
+                return;
+            }
+        };
+
+        // the timeline keeps the world of the window it's played in
+        let world_bounds = match self.camera.fixed_world_bounds() {
+            Some(world_bounds) => format!(" --world-bounds {},{}", world_bounds.x, world_bounds.y),
+            None => String::new(),
+        };
+
+        println!(
+            "--count {} --seed {} --threads {} --quadtree-capacity {}{} --timeline {}",
+            self.simulation.boid_count(), self.simulation.seed(), self.simulation.thread_count, self.simulation.index_capacity, world_bounds, PARAMS_PATH,
+        );
+        println!("{} holding:\n{}", PARAMS_PATH, timeline);
+    }
+
+    /// a single update per call, or as many as passed at the fixed `--update-rate`
+    pub fn update(&mut self) {
+        let Some(update_rate) = self.update_rate else {
+            self.step();
+            return;
+        };
+
+        let interval = Duration::from_secs_f32(1.0 / update_rate);
+        let now = Instant::now();
+
+        // a simulation too slow for the rate falls behind instead of trying to catch up with ever more updates
+        if let Some(last_update) = self.last_update {
+            self.update_lag = (self.update_lag + (now - last_update)).min(interval * MAX_UPDATES_PER_FRAME);
+        }
+
+        self.last_update = Some(now);
+
+        while self.update_lag >= interval {
+            self.previous_locations.clear();
+            self.previous_locations.extend(self.simulation.boids().iter().map(|boid| boid.location.clone()));
+
+            self.step();
+            self.update_lag -= interval;
+        }
+    }
+
+    /// how far rendering is between the previous and the current update with a fixed `--update-rate`
+    ///
+    /// [None] draws the boids where they currently are, also while paused or after the boid count changed
+    fn interpolation(&self) -> Option<f32> {
+        let update_rate = self.update_rate.filter(|_| !self.paused && self.previous_locations.len() == self.simulation.boids().len())?;
+
+        Some((self.update_lag.as_secs_f32() * update_rate).min(1.0))
+    }
+
+    fn step(&mut self) {
+        if let Some(replay) = &mut self.replay {
+            for event in replay.events_until(self.frame) {
+                self.handle_input(&event);
+            }
+        }
+
+        // replay events are applied first, they are the ones pausing and stepping
+        if self.paused && !self.step_once {
+            return;
+        }
+
+        self.step_once = false;
 
-                new_vel
-            }));
+        if let Some(timeline) = &self.timeline {
+            self.simulation.params = timeline.params_at(self.simulation.frame(), &self.simulation.params);
         }
 
-        let mut index = 0;
+        self.follow_waypoints();
 
-        let mut new_vels = Vec::new();
+        // leaders follow the mouse, once it leaves the window they fall back to their path
+        self.simulation.leader_target = self.cursor_world();
 
-        for thread in threads {
-            new_vels.push(thread.join().expect("TODO: panic message"));
+        if let Some(brush) = self.brush() {
+            self.simulation.apply_brush(&brush);
         }
 
-        self.update_time.0 = (start_time.elapsed().as_nanos() + self.update_time.0 * 59) / 60;
         let start_time = Instant::now();
 
-        for mut vec in new_vels {
-            for boid_vel in &mut vec {
-                let boid = self.boids.get_mut(index).unwrap();
+        self.simulation.update();
+
+        self.stats.record_update(start_time.elapsed().as_secs_f64() * 1000.0, self.boid_count());
+        self.updated = true;
+
+        // stepping while inspecting follows the boid
+        if let Some((index, _)) = &self.inspection {
+            let index = *index;
+            self.inspection = self.simulation.inspect(index).map(|inspection| (index, inspection));
+        }
+
+        // the metric needs a query per boid, refreshing it twice a second is plenty
+        if self.frame.is_multiple_of(30) {
+            self.nearest_distance = self.simulation.average_nearest_distance();
+        }
+
+        self.frame += 1;
+    }
+
+    /// points the simulation at the current waypoint, advancing it once the flock got there
+    fn follow_waypoints(&mut self) {
+        if self.waypoints.is_empty() {
+            self.simulation.goal = None;
+            return;
+        }
+
+        if let Some(mut centroid) = self.simulation.centroid() {
+            centroid.sub(&self.waypoints[self.current_waypoint]);
+
+            if centroid.length() < WAYPOINT_REACHED_DISTANCE {
+                self.current_waypoint = (self.current_waypoint + 1) % self.waypoints.len();
+            }
+        }
 
-                boid.add_vel(boid_vel, 0.6);
-                boid.update();
+        self.simulation.goal = Some(self.waypoints[self.current_waypoint].clone());
+    }
 
-                index += 1;
+    /// the next surface texture, [None] if acquiring it kept timing out and the frame gets dropped
+    ///
+    /// wgpu has no way to pass a shorter timeout, each attempt waits as long as the backend does
+    fn acquire_frame(&mut self) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+        for _ in 0..FRAME_ACQUIRE_ATTEMPTS {
+            match self.surface.get_current_texture() {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(wgpu::SurfaceError::Timeout) => continue,
+                Err(err) => return Err(err),
             }
         }
 
-        self.update_time.1 = (start_time.elapsed().as_nanos() + self.update_time.1 * 59) / 60;
+        self.stats.record_dropped_frame();
+        Ok(None)
+    }
+
+    /// the RGBA bytes of the next rendered frame, rows from top to bottom at the window size without any padding
+    ///
+    /// it resolves during a later [State::render] once the GPU copy was read back, so it needs to be polled alongside
+    /// the event loop and blocking on it from there never finishes. it resolves to no bytes if the window format
+    /// can't be read back or the [State] is dropped first
+    pub fn capture_frame(&mut self) -> impl Future<Output=Vec<u8>> {
+        let (request, capture) = capture_request();
+
+        if FrameReadback::supports(self.config.format) {
+            self.capture_requests.push(request);
+        }
+
+        capture
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let frame = self.surface.get_current_texture()?;
+    pub fn render(&mut self, window: &Window) -> Result<(), wgpu::SurfaceError> {
+        // a timeout under heavy load isn't fatal, the next frame just tries again
+        let Some(frame) = self.acquire_frame()? else {
+            return Ok(());
+        };
+
+        let surface_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        if let Some(captured) = self.frame_readback.read(&self.device) {
+            for request in self.pending_captures.drain(..) {
+                request.fulfill(captured.rgba.clone());
+            }
+
+            if let Some(stream) = self.stream.as_ref().filter(|_| self.pending_stream_frame) {
+                stream.send(captured);
+            }
+        }
+
+        let stream_frame = self.frame_readback.is_idle() && self.stream.as_mut().is_some_and(|stream| stream.wants_frame());
+        let capture = self.frame_readback.is_idle() && (stream_frame || !self.capture_requests.is_empty());
+
+        let capture_target = if capture {
+            self.pending_captures = std::mem::take(&mut self.capture_requests);
+            self.pending_stream_frame = stream_frame;
 
-        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let target = self.capture_target.take();
+            Some(target.unwrap_or_else(|| create_target(&self.device, &self.texture_bind_group_layout, self.size.width, self.size.height, self.config.format, "Capture Target")))
+        } else {
+            None
+        };
+        let view = match &capture_target {
+            Some((target, _)) => &target.view,
+            None => &surface_view,
+        };
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
@@ -290,84 +1188,496 @@ impl State {
         let mut vertices = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
-        for (index, boid) in self.boids.iter().enumerate() {
-            boid.create_buffer(&mut vertices, &mut indices, index as u32);
+        let (render_mode, vertex_count) = self.capped_render_mode(self.effective_vertex_count());
+
+        // indices can outlive a change of the boid count, those are ignored
+        let mut is_selected = vec![false; self.simulation.boids().len()];
+        for &index in &self.selected {
+            if let Some(is_selected) = is_selected.get_mut(index) {
+                *is_selected = true;
+            }
         }
 
-        let vertex_buffer = self.device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
+        let (view_min, view_max) = self.camera.visible_bounds();
+        let is_visible = |location: &Vec2| {
+            location.x >= view_min.x - CULL_MARGIN && location.x <= view_max.x + CULL_MARGIN
+                && location.y >= view_min.y - CULL_MARGIN && location.y <= view_max.y + CULL_MARGIN
+        };
+
+        let interpolation = self.interpolation();
+        let world_bounds = &self.simulation.params.world_bounds;
+        let mut interpolated;
+
+        for (index, (boid, is_selected)) in self.simulation.boids().iter().zip(is_selected).enumerate() {
+            // drawn between the last two updates, otherwise a fixed update rate stutters against the framerate
+            let boid = match (interpolation, self.previous_locations.get(index)) {
+                // a boid that just wrapped around would cross the whole world on the way
+                (Some(t), Some(previous)) if (boid.location.x - previous.x).abs() < world_bounds.x && (boid.location.y - previous.y).abs() < world_bounds.y => {
+                    interpolated = boid.clone();
+                    interpolated.location = previous.lerp(&boid.location, t);
+                    &interpolated
+                }
+                _ => boid,
+            };
+
+            if self.cull_offscreen && !is_visible(&boid.location) {
+                continue;
             }
-        );
 
-        let index_buffer = self.device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(&indices),
-                usage: wgpu::BufferUsages::INDEX,
+            let color = if is_selected {
+                SELECTED_COLOR
+            } else if boid.pinned {
+                PINNED_COLOR
+            } else if boid.is_leader {
+                LEADER_COLOR
+            } else {
+                self.color_mode.color(boid)
+            };
+
+            match render_mode {
+                RenderMode::Circles => boid.create_circle_buffer(&mut vertices, &mut indices, color),
+                RenderMode::Shapes => boid.create_buffer(&mut vertices, &mut indices, vertex_count, self.simulation.params.max_speed, color),
+                RenderMode::Sprites => boid.create_sprite_buffer(&mut vertices, &mut indices, color),
+                RenderMode::Points => boid.create_point_buffer(&mut vertices, &mut indices, color),
             }
-        );
+        }
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let boid_mesh = Mesh::new(&self.device, "Boid", &vertices, &indices);
+
+        let mut vertices = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        // show the perception area of the boid closest to the cursor
+        if let Some(boid) = self.hovered.and_then(|index| self.simulation.boids().get(index)) {
+            push_circle(&mut vertices, &mut indices, &boid.location, self.simulation.params.perception_radius, 64, [1.0, 1.0, 1.0, 0.15]);
+        }
+
+        if let Some((boid, inspection)) = self.inspection.as_ref().and_then(|(index, inspection)| Some((self.simulation.boids().get(*index)?, inspection))) {
+            push_circle(&mut vertices, &mut indices, &boid.location, self.simulation.params.perception_radius, 64, [1.0, 1.0, 1.0, 0.15]);
+
+            for neighbor_boid in inspection.neighbors.iter().filter_map(|index| self.simulation.boids().get(*index)) {
+                push_circle(&mut vertices, &mut indices, &neighbor_boid.location, SIZE * neighbor_boid.scale * 2.0, 8, INSPECTED_NEIGHBOR_COLOR);
+            }
+
+            push_circle(&mut vertices, &mut indices, &boid.location, SIZE * boid.scale * 3.0, 16, [1.0, 1.0, 1.0, 0.8]);
+        }
+
+        for boid in self.simulation.boids().iter().filter(|boid| boid.is_leader) {
+            push_circle(&mut vertices, &mut indices, &boid.location, SIZE * boid.scale * 4.0, 16, [LEADER_COLOR[0], LEADER_COLOR[1], LEADER_COLOR[2], 0.3]);
+        }
+
+        for (index, waypoint) in self.waypoints.iter().enumerate() {
+            let alpha = if index == self.current_waypoint { 1.0 } else { 0.4 };
+            push_circle(&mut vertices, &mut indices, waypoint, 0.01, 4, [1.0, 0.8, 0.2, alpha]);
+        }
+
+        // part of the overlay, hidden along with the text for clean recordings
+        let legend_labels = self.color_mode.legend_labels().filter(|_| self.show_overlay);
+
+        let (legend_margin, legend_size) = (LEGEND_MARGIN * self.scale_factor, (LEGEND_SIZE.0 * self.scale_factor, LEGEND_SIZE.1 * self.scale_factor));
+
+        // in the lower left corner of the world rather than of the window, which may be a letterbox bar
+        let [viewport_x, viewport_y, _, viewport_height] = self.camera.viewport(self.size);
+        let (legend_left, legend_bottom) = (viewport_x + legend_margin, viewport_y + viewport_height - legend_margin);
+
+        if legend_labels.is_some() {
+            let min = self.camera.screen_to_world((legend_left as f64, legend_bottom as f64), self.size);
+            let max = self.camera.screen_to_world(((legend_left + legend_size.0) as f64, (legend_bottom - legend_size.1) as f64), self.size);
+
+            let color_mode = self.color_mode;
+            push_gradient_strip(&mut vertices, &mut indices, &min, &max, 32, |t| color_mode.gradient(t));
+        }
 
-        render_pass.set_pipeline(&self.render_pipeline); // 2.
+        let overlay_mesh = Mesh::new(&self.device, "Overlay", &vertices, &indices);
+
+        let mut vertices = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        // the default boundary is the window border itself
+        if let Some(boundary) = &self.simulation.params.boundary {
+            push_line_loop(&mut vertices, &mut indices, &boundary.outline(), [1.0, 1.0, 1.0, 0.3]);
+        }
+
+        if let (Some(start), Some(end)) = (&self.selection_start, self.cursor_world()) {
+            let (min, max) = selection_rect(start, &end);
+            let corners = [Vec2::new(min.x, min.y), Vec2::new(max.x, min.y), Vec2::new(max.x, max.y), Vec2::new(min.x, max.y)];
+
+            push_line_loop(&mut vertices, &mut indices, &corners, SELECTED_COLOR);
+        }
+
+        if let Some(brush) = self.brush() {
+            push_line_loop(&mut vertices, &mut indices, &brush.outline(64), BRUSH_COLOR);
+        }
+
+        if let Some((boid, inspection)) = self.inspection.as_ref().and_then(|(index, inspection)| Some((self.simulation.boids().get(*index)?, inspection))) {
+            // the strongest behavior reaches the edge of the perception circle, the others are drawn relative to it
+            let longest = inspection.steering.iter().map(Vec2::length).fold(0.0, f32::max);
+
+            for (steering, (color, _)) in inspection.steering.iter().zip(STEERING_COLORS.iter().cycle()).filter(|_| longest > 0.0) {
+                let mut end = steering.clone();
+                end.mul(self.simulation.params.perception_radius / longest);
+                end.add(&boid.location);
+
+                push_arrow(&mut vertices, &mut indices, &boid.location, &end, *color);
+            }
+        }
+
+        if let Some(trails) = &self.simulation.trails {
+            let world_bounds = &self.simulation.params.world_bounds;
+
+            for (index, boid) in self.simulation.boids().iter().enumerate() {
+                let color = self.color_mode.color(boid);
+                let mut trail = trails.trail(index).peekable();
+                let mut age = 0;
+
+                while let (Some(newer), Some(&older)) = (trail.next(), trail.peek()) {
+                    let alpha = 0.6 * (1.0 - age as f32 / trails.length() as f32);
+
+                    // a boid wrapping around jumps across the whole world, that part of its path isn't a line
+                    if (newer.x - older.x).abs() < world_bounds.x && (newer.y - older.y).abs() < world_bounds.y {
+                        push_line(&mut vertices, &mut indices, newer, older, [color[0], color[1], color[2], alpha]);
+                    }
+
+                    age += 1;
+                }
+            }
+        }
 
-        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        if self.show_velocities {
+            let boids = self.simulation.boids();
 
-        render_pass.draw_indexed(0..indices.len() as u32, 0, 0..1); // 3.
+            for boid in boids.iter().step_by(boids.len().div_ceil(MAX_VELOCITY_LINES).max(1)) {
+                let mut end = boid.vel.clone();
+                end.normalize();
+                end.mul(self.velocity_line_length);
+                end.add(&boid.location);
+
+                push_line(&mut vertices, &mut indices, &boid.location, &end, [0.3, 1.0, 0.3, 0.6]);
+            }
+        }
+
+        let line_mesh = Mesh::new(&self.device, "Lines", &vertices, &indices);
+
+        if let Some(gpu_timer) = &self.gpu_timer {
+            gpu_timer.begin(&mut encoder);
+        }
+
+        // drawn at a lower resolution first and scaled up as the background of the full resolution pass
+        if let Some((render_target, _)) = &self.render_target {
+            let mut render_pass = begin_clear_pass(&mut encoder, &render_target.view, "Boid Pass");
+
+            self.set_viewport(&mut render_pass, scaled_size(self.size, self.render_scale));
+            self.draw_boids(&mut render_pass, render_mode, &boid_mesh);
+        }
+
+        let mut render_pass = begin_clear_pass(&mut encoder, view, "Render Pass");
+
+        // the scaled up target already has the bars, so it covers the whole window
+        match &self.render_target {
+            Some((_, bind_group)) => {
+                self.draw_blit(&mut render_pass, bind_group);
+                self.set_viewport(&mut render_pass, self.size);
+            }
+            None => {
+                self.set_viewport(&mut render_pass, self.size);
+                self.draw_boids(&mut render_pass, render_mode, &boid_mesh);
+            }
+        }
+
+        render_pass.set_pipeline(&self.line_pipeline);
+        line_mesh.draw(&mut render_pass);
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        overlay_mesh.draw(&mut render_pass);
 
         drop(render_pass);
 
-        let render_time = self.render_time as f64 / 1_000_000.0;
-        let update_time = (self.update_time.0 as f64 / 1_000_000.0, self.update_time.1 as f64 / 1_000_000.0);
-        let sum = render_time + update_time.1 + update_time.0;
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.end(&mut encoder);
+
+            if let Some(elapsed) = gpu_timer.read(&self.device) {
+                self.gpu_render_time.push(elapsed);
+            }
+        }
+
+        let render_time = &self.render_time;
+        let index_time = &self.simulation.index_time;
+        let steer_time = &self.simulation.steer_time;
+        let write_back_time = &self.simulation.write_back_time;
+        let sum = render_time.average() + index_time.average() + steer_time.average() + write_back_time.average();
         let fps = 1000.0 / sum;
 
-        self.glyph_brush.queue(Section {
-            screen_position: (10.0, 10.0),
-            bounds: (self.size.width as f32, self.size.height as f32),
-            text: vec![Text::new(format!("render: {:.1}ms\nupdate: {:.1}/{:.1}ms\nsum: {:.1}ms\nmax fps: {:.1}", render_time, update_time.0, update_time.1, sum, fps).as_str())
-                .with_color([1.0, 1.0, 1.0, 1.0])
-                .with_scale(20.0)],
-            ..Section::default()
+        // without a font the boids are still drawn, just without any text
+        if let Some(glyph_brush) = &mut self.glyph_brush {
+            if self.show_overlay {
+                let mut text = String::new();
+
+                writeln!(text, "render: {:.1}ms ({:.1}-{:.1})", render_time.average(), render_time.min(), render_time.max()).unwrap();
+                match &self.gpu_timer {
+                    Some(_) => writeln!(text, "gpu render: {:.2}ms", self.gpu_render_time.average()).unwrap(),
+                    None => writeln!(text, "gpu render: N/A").unwrap(),
+                }
+                writeln!(text, "update: {:.1}/{:.1}/{:.1}ms (index/steer/write back)", index_time.average(), steer_time.average(), write_back_time.average()).unwrap();
+                writeln!(text, "sum: {:.1}ms", sum).unwrap();
+                writeln!(text, "steer threads: {:.1}/{:.1}ms (slowest/mean{})", self.simulation.slowest_thread_time.average(), self.simulation.mean_thread_time.average(), if self.simulation.balance_threads { ", balanced" } else { "" }).unwrap();
+                writeln!(text, "max fps: {:.1}", fps).unwrap();
+                writeln!(text, "vertices: {}{}", vertex_count, if self.auto_vertex_count { " (auto)" } else { "" }).unwrap();
+                writeln!(text, "radius: {:.3}", self.simulation.params.perception_radius).unwrap();
+                writeln!(text, "quad-tree capacity: {}", self.simulation.index_capacity).unwrap();
+                writeln!(text, "index age: {}/{} steps", self.simulation.index_age(), self.simulation.rebuild_interval).unwrap();
+                writeln!(text, "time scale: {}", self.simulation.time_scale).unwrap();
+                if let Some(update_rate) = self.update_rate {
+                    writeln!(text, "update rate: {}/s", update_rate).unwrap();
+                }
+                writeln!(text, "boundary: {:?}{}", self.simulation.params.boundary_mode, if self.simulation.boundary_transitioning() { " (switching)" } else { "" }).unwrap();
+                writeln!(text, "zoom: {:.2}", self.camera.zoom()).unwrap();
+                writeln!(text, "render scale: {:.2}", self.render_scale).unwrap();
+                writeln!(text, "present mode: {:?}", self.config.present_mode).unwrap();
+                writeln!(text, "dropped frames: {}", self.stats.dropped_frames()).unwrap();
+                write!(text, "nearest neighbor: {:.4}", self.nearest_distance).unwrap();
+
+                if self.show_adapter_info {
+                    write!(text, "\ngpu: {} ({:?}, {:?})", self.adapter_info.name, self.adapter_info.backend, self.adapter_info.device_type).unwrap();
+                }
+
+                if let Some(index) = self.preset {
+                    write!(text, "\npreset: {}", PRESETS[index].name).unwrap();
+                }
+
+                if let Some((rule, _)) = self.isolated_rule {
+                    write!(text, "\n{} only", rule.name()).unwrap();
+                }
+
+                if let Some((index, inspection)) = &self.inspection {
+                    write!(text, "\ninspecting boid {}, {} neighbors", index, inspection.neighbors.len()).unwrap();
+
+                    for ((behavior, steering), (_, color)) in self.simulation.behaviors.iter().zip(&inspection.steering).zip(STEERING_COLORS.iter().cycle()) {
+                        write!(text, "\n{} ({}): {:.4}", behavior.name(), color, steering.length()).unwrap();
+                    }
+                }
+
+                if !self.selected.is_empty() {
+                    write!(text, "\nselected: {}", self.selected.len()).unwrap();
+                }
+
+                if self.color_mode == ColorMode::Threads {
+                    if self.simulation.runs_parallel() {
+                        write!(text, "\ncolored by steering thread{}", if self.simulation.balance_threads { ", balanced" } else { "" }).unwrap();
+                    } else {
+                        write!(text, "\ncolored by steering thread, running on one").unwrap();
+                    }
+                }
+
+                if render_mode != self.render_mode {
+                    write!(text, "\nvertex cap reached, drawing points").unwrap();
+                }
+
+                if self.simulation.freeze_index {
+                    write!(text, "\nstale index").unwrap();
+                }
+
+                if self.paused {
+                    write!(text, "\npaused").unwrap();
+                }
+
+                glyph_brush.queue(Section {
+                    screen_position: (10.0 * self.scale_factor, 10.0 * self.scale_factor),
+                    bounds: (self.size.width as f32, self.size.height as f32),
+                    text: vec![Text::new(&text)
+                        .with_color([1.0, 1.0, 1.0, 1.0])
+                        .with_scale(20.0 * self.scale_factor)],
+                    ..Section::default()
+                });
+            }
+
+            if let (Some(index), Some(cursor), true) = (self.hovered, self.cursor, self.show_overlay) {
+                if let Some(boid) = self.simulation.boids().get(index) {
+                    let mut velocity = boid.vel.clone();
+                    velocity.mul(self.simulation.params.max_speed * boid.speed);
+
+                    let text = format!("boid {}\nposition: {:.3}\nvelocity: {:.4}\nneighbors: {}", index, boid.location, velocity, boid.neighbor_count);
+
+                    glyph_brush.queue(Section {
+                        screen_position: (cursor.x as f32 + TOOLTIP_OFFSET * self.scale_factor, cursor.y as f32 + TOOLTIP_OFFSET * self.scale_factor),
+                        text: vec![Text::new(&text)
+                            .with_color([1.0, 1.0, 1.0, 1.0])
+                            .with_scale(18.0 * self.scale_factor)],
+                        ..Section::default()
+                    });
+                }
+            }
+
+            if let Some((min_label, max_label)) = legend_labels {
+                let y = legend_bottom - legend_size.1 - 20.0 * self.scale_factor;
+
+                glyph_brush.queue(Section {
+                    screen_position: (legend_left, y),
+                    text: vec![Text::new(min_label)
+                        .with_color([1.0, 1.0, 1.0, 1.0])
+                        .with_scale(18.0 * self.scale_factor)],
+                    ..Section::default()
+                });
+
+                glyph_brush.queue(Section {
+                    screen_position: (legend_left + legend_size.0, y),
+                    text: vec![Text::new(max_label)
+                        .with_color([1.0, 1.0, 1.0, 1.0])
+                        .with_scale(18.0 * self.scale_factor)],
+                    layout: Layout::default_single_line().h_align(HorizontalAlign::Right),
+                    ..Section::default()
+                });
+            }
+
+            // Draw the text!
+            glyph_brush.draw_queued(
+                &self.device,
+                &mut self.staging_belt,
+                &mut encoder,
+                view,
+                self.size.width,
+                self.size.height,
+            )
+                .expect("Draw queued");
+        }
+
+        let simulation = &mut self.simulation;
+        let show_overlay = &mut self.show_overlay;
+        let show_velocities = &mut self.show_velocities;
+        let cull_offscreen = &mut self.cull_offscreen;
+        let velocity_line_length = &mut self.velocity_line_length;
+        let trail_length = &mut self.trail_length;
+        let mut new_world_bounds = None;
+
+        let gui_command_buffers = self.gui.render(window, &self.device, &self.queue, &mut encoder, view, |ctx| {
+            egui::Window::new("Parameters").show(ctx, |ui| {
+                let params = &mut simulation.params;
+
+                ui.add(egui::Slider::new(&mut params.perception_radius, MIN_PERCEPTION_RADIUS..=MAX_PERCEPTION_RADIUS).text("perception radius"));
+                ui.add(egui::Slider::new(&mut params.separation_radius, MIN_PERCEPTION_RADIUS..=MAX_PERCEPTION_RADIUS).text("separation radius"));
+                ui.add(egui::Slider::new(&mut params.separation_weight, 0.0..=5.0).text("separation"));
+                ui.add(egui::Slider::new(&mut params.alignment_weight, 0.0..=5.0).text("alignment"));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut params.alignment_mode, AlignmentMode::Heading, "align headings");
+                    ui.radio_value(&mut params.alignment_mode, AlignmentMode::Velocity, "align velocities");
+                });
+                ui.add(egui::Slider::new(&mut params.cohesion_weight, 0.0..=5.0).text("cohesion"));
+                ui.add(egui::Slider::new(&mut params.min_neighbors_for_alignment, 0..=10).text("min neighbors to align"));
+                ui.add(egui::Slider::new(&mut params.min_neighbors_for_cohesion, 0..=10).text("min neighbors for cohesion"));
+                ui.add(egui::Slider::new(&mut params.separation_exponent, 0..=8).text("separation falloff"));
+                ui.add(egui::Slider::new(&mut params.alignment_exponent, 0..=8).text("alignment falloff"));
+                ui.add(egui::Slider::new(&mut params.cohesion_exponent, 0..=8).text("cohesion falloff"));
+                ui.add(egui::Slider::new(&mut params.max_speed, 0.0..=0.02).text("max speed"));
+                ui.add(egui::Slider::new(&mut params.substeps, 1..=8).text("substeps"));
+                ui.add(egui::Slider::new(&mut params.jitter, 0.0..=1.0).text("jitter"));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut params.jitter_mode, JitterMode::White, "white noise jitter");
+                    ui.radio_value(&mut params.jitter_mode, JitterMode::Coherent, "coherent wander");
+                });
+                ui.add(egui::Slider::new(&mut params.wander_strength, 0.0..=2.0).text("wander strength"));
+                ui.add(egui::Slider::new(&mut params.drag, 0.0..=0.5).text("drag"));
+                ui.add(egui::Slider::new(&mut params.smoothing, 0.0..=0.95).text("smoothing"));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut params.boundary_mode, BoundaryMode::Bounce, "bounce off edges");
+                    ui.radio_value(&mut params.boundary_mode, BoundaryMode::Wrap, "wrap around");
+                });
+                ui.add(egui::Slider::new(&mut params.edge_force, 0.0..=5.0).text("edge force"));
+                ui.add(egui::Slider::new(&mut params.edge_margin, 0.0..=0.5).text("edge margin"));
+
+                let mut world_bounds = params.world_bounds.clone();
+                let width_changed = ui.add(egui::Slider::new(&mut world_bounds.x, WORLD_BOUNDS_RANGE.0..=WORLD_BOUNDS_RANGE.1).logarithmic(true).text("world half width")).changed();
+                let height_changed = ui.add(egui::Slider::new(&mut world_bounds.y, WORLD_BOUNDS_RANGE.0..=WORLD_BOUNDS_RANGE.1).logarithmic(true).text("world half height")).changed();
+
+                if width_changed || height_changed {
+                    new_world_bounds = Some(world_bounds);
+                }
+                ui.add(egui::Slider::new(&mut params.center_pull, 0.0..=1.0).text("center pull"));
+
+                let mut boid_count = simulation.boid_count();
+                if ui.add(egui::Slider::new(&mut boid_count, 0..=50000).logarithmic(true).text("boids")).changed() {
+                    simulation.set_boid_count(boid_count);
+                }
+
+                ui.checkbox(&mut simulation.params.resolve_collisions, "resolve collisions");
+                ui.horizontal(|ui| {
+                    ui.label("spawn (on reset)");
+                    ui.radio_value(&mut simulation.params.spawn_pattern, SpawnPattern::Disc, "disc");
+                    ui.radio_value(&mut simulation.params.spawn_pattern, SpawnPattern::ColorBands, "color bands");
+                });
+
+                let mut aging = simulation.params.max_age.is_some();
+                let mut max_age = simulation.params.max_age.unwrap_or(DEFAULT_MAX_AGE);
+                ui.checkbox(&mut aging, "boids die of age");
+                ui.add_enabled(aging, egui::Slider::new(&mut max_age, 10..=10000).logarithmic(true).text("max age"));
+                simulation.params.max_age = aging.then_some(max_age);
+
+                ui.add(egui::Slider::new(&mut simulation.params.birth_rate, 0.0..=100.0).logarithmic(true).text("births per update"));
+                ui.add(egui::Slider::new(&mut simulation.params.max_population, 0..=50000).logarithmic(true).text("max population"));
+                ui.checkbox(&mut simulation.balance_threads, "balance threads");
+                ui.add(egui::Slider::new(&mut simulation.rebuild_interval, 1..=30).text("index rebuild interval"));
+                ui.checkbox(show_overlay, "stats overlay");
+                ui.checkbox(show_velocities, "velocity lines");
+                ui.checkbox(cull_offscreen, "cull offscreen boids");
+                ui.add(egui::Slider::new(velocity_line_length, 0.0..=MAX_VELOCITY_LINE_LENGTH).text("velocity line length"));
+
+                let mut show_trails = simulation.trails.is_some();
+                let trails_changed = ui.checkbox(&mut show_trails, "trails").changed();
+                let length_changed = ui.add(egui::Slider::new(trail_length, 2..=MAX_TRAIL_LENGTH).text("trail length")).changed();
+
+                if trails_changed || (length_changed && show_trails) {
+                    simulation.trails = show_trails.then(|| Trails::new(*trail_length));
+                }
+            });
         });
 
-        // Draw the text!
-        self.glyph_brush.draw_queued(
-            &self.device,
-            &mut self.staging_belt,
-            &mut encoder,
-            &view,
-            self.size.width,
-            self.size.height,
-        )
-            .expect("Draw queued");
+        // the camera can't be borrowed by the panel along with the simulation
+        if let Some(world_bounds) = new_world_bounds {
+            self.set_world_bounds(Some(world_bounds));
+        }
+
+        if let Some((target, bind_group)) = &capture_target {
+            self.frame_readback.copy(&self.device, &mut encoder, &target.texture, self.size.width, self.size.height, self.config.format);
 
+            let mut render_pass = begin_clear_pass(&mut encoder, &surface_view, "Present Pass");
+            self.draw_blit(&mut render_pass, bind_group);
+        }
 
         self.staging_belt.finish();
         // submit will accept anything that implements IntoIter
-        self.queue.submit([encoder.finish()]);
+        self.queue.submit(gui_command_buffers.into_iter().chain([encoder.finish()]));
+
+        self.frame_readback.submitted();
+
+        if capture_target.is_some() {
+            self.capture_target = capture_target;
+        }
+
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.submitted();
+        }
+
+        let elapsed = start_time.elapsed().as_secs_f64() * 1000.0;
+        self.render_time.push(elapsed);
+        self.stats.record_render(elapsed);
+
+        if let Some(perf_log) = &mut self.perf_log {
+            let simulation = &self.simulation;
+            let update_time = |time: &TimingWindow| time.last().filter(|_| self.updated);
+
+            let times = FrameTimes {
+                frame: self.stats.frames(),
+                index: update_time(&simulation.index_time),
+                steer: update_time(&simulation.steer_time),
+                write_back: update_time(&simulation.write_back_time),
+                render: elapsed,
+            };
+
+            if let Err(err) = perf_log.write(&times) {
+                eprintln!("failed to write the performance log, stopped logging: {}", err);
+                self.perf_log = None;
+            }
+        }
 
-        self.render_time = (start_time.elapsed().as_nanos() + self.render_time * 59) / 60;
+        self.updated = false;
 
         frame.present();
 
@@ -376,4 +1686,80 @@ impl State {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// the adapter picked by `choice`, retrying with a software adapter if no GPU is compatible with `surface`
+async fn request_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface, choice: AdapterChoice) -> Option<wgpu::Adapter> {
+    let power_preference = match choice {
+        AdapterChoice::LowPower => wgpu::PowerPreference::LowPower,
+        AdapterChoice::HighPerformance | AdapterChoice::Fallback => wgpu::PowerPreference::HighPerformance,
+    };
+
+    let options = |force_fallback_adapter| wgpu::RequestAdapterOptions {
+        power_preference,
+        compatible_surface: Some(surface),
+        force_fallback_adapter,
+    };
+
+    if choice != AdapterChoice::Fallback {
+        if let Some(adapter) = instance.request_adapter(&options(false)).await {
+            return Some(adapter);
+        }
+
+        log::warn!("no compatible GPU was found, trying a software adapter");
+    }
+
+    instance.request_adapter(&options(true)).await
+}
+
+/// a render pass into `view`, cleared to black first
+fn begin_clear_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::TextureView, label: &str) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 1.0,
+                }),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    })
+}
+
+/// the target the boids get drawn into at `render_scale` of `size`, [None] at full resolution
+fn create_render_target(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, size: winit::dpi::PhysicalSize<u32>, render_scale: f32, format: wgpu::TextureFormat) -> Option<(Texture, wgpu::BindGroup)> {
+    if render_scale >= 1.0 {
+        return None;
+    }
+
+    let size = scaled_size(size, render_scale);
+
+    Some(create_target(device, layout, size.width, size.height, format, "Render Target"))
+}
+
+/// at least a pixel on both axes, even a thin window at a low render scale stays a valid texture size
+fn scaled_size(size: winit::dpi::PhysicalSize<u32>, scale: f32) -> winit::dpi::PhysicalSize<u32> {
+    let scale = |length: u32| ((length as f32 * scale).round() as u32).max(1);
+
+    winit::dpi::PhysicalSize::new(scale(size.width), scale(size.height))
+}
+
+/// a texture to draw into, bound for sampling it afterwards
+fn create_target(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, width: u32, height: u32, format: wgpu::TextureFormat, label: &str) -> (Texture, wgpu::BindGroup) {
+    let texture = Texture::render_target(device, width, height, format, label);
+    let bind_group = texture.bind_group(device, layout);
+
+    (texture, bind_group)
+}
+
+/// the lower left and upper right corner of the rectangle spanned by `a` and `b`
+fn selection_rect(a: &Vec2, b: &Vec2) -> (Vec2, Vec2) {
+    (Vec2::new(a.x.min(b.x), a.y.min(b.y)), Vec2::new(a.x.max(b.x), a.y.max(b.y)))
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// samples kept by a [TimingWindow] by default, about a second at 60 fps
+pub const TIMING_WINDOW_SIZE: usize = 60;
+
+/// aggregated performance of a whole run, printed on exit
+#[derive(Debug, Default)]
+pub struct RunStats {
+    frames: u64,
+    /// frames skipped because the surface texture couldn't be acquired in time
+    dropped_frames: u64,
+    /// per frame samples in milliseconds
+    update_times: Vec<f64>,
+    render_times: Vec<f64>,
+    peak_boid_count: usize,
+}
+
+impl RunStats {
+    pub fn record_update(&mut self, milliseconds: f64, boid_count: usize) {
+        self.update_times.push(milliseconds);
+        self.peak_boid_count = self.peak_boid_count.max(boid_count);
+    }
+
+    pub fn record_render(&mut self, milliseconds: f64) {
+        self.render_times.push(milliseconds);
+        self.frames += 1;
+    }
+
+    pub fn record_dropped_frame(&mut self) {
+        self.dropped_frames += 1;
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+}
+
+/// the most recent timing samples in milliseconds
+#[derive(Debug, Clone)]
+pub struct TimingWindow {
+    samples: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl TimingWindow {
+    pub fn new(capacity: usize) -> TimingWindow {
+        TimingWindow {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, milliseconds: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(milliseconds);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.samples.back().copied()
+    }
+
+    /// all statistics are 0 while there are no samples
+    pub fn min(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::min).unwrap_or(0.0)
+    }
+
+    pub fn average(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        self.samples.iter().sum::<f64>() / self.samples.len() as f64
+    }
+
+    pub fn max(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::max).unwrap_or(0.0)
+    }
+}
+
+impl Default for TimingWindow {
+    fn default() -> Self {
+        TimingWindow::new(TIMING_WINDOW_SIZE)
+    }
+}
+
+/// upper bounds of the [NeighborHistogram] buckets, the last bucket is open ended
+const NEIGHBOR_BUCKETS: [usize; 4] = [0, 5, 20, 50];
+
+/// how many boids have a given number of neighbors
+#[derive(Debug, Default, Clone)]
+pub struct NeighborHistogram {
+    buckets: [usize; NEIGHBOR_BUCKETS.len() + 1],
+}
+
+impl NeighborHistogram {
+    pub fn add(&mut self, neighbor_count: usize) {
+        let bucket = NEIGHBOR_BUCKETS.iter()
+            .position(|max| neighbor_count <= *max)
+            .unwrap_or(NEIGHBOR_BUCKETS.len());
+
+        self.buckets[bucket] += 1;
+    }
+}
+
+impl fmt::Display for NeighborHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "neighbors:")?;
+
+        let mut min = 0;
+
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            let label = match NEIGHBOR_BUCKETS.get(bucket) {
+                Some(&max) if max == min => format!("{}", max),
+                Some(&max) => format!("{}-{}", min, max),
+                None => format!("{}+", min),
+            };
+
+            write!(f, "  {:>6}: {}", label, count)?;
+
+            if bucket + 1 < self.buckets.len() {
+                writeln!(f)?;
+            }
+
+            if let Some(&max) = NEIGHBOR_BUCKETS.get(bucket) {
+                min = max + 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn average(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn median(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let middle = sorted.len() / 2;
+
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "frames: {}", self.frames)?;
+        writeln!(f, "dropped frames: {}", self.dropped_frames)?;
+        writeln!(f, "update: avg {:.2}ms, median {:.2}ms", average(&self.update_times), median(&self.update_times))?;
+        writeln!(f, "render: avg {:.2}ms, median {:.2}ms", average(&self.render_times), median(&self.render_times))?;
+        write!(f, "peak boids: {}", self.peak_boid_count)
+    }
+}
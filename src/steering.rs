@@ -0,0 +1,574 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::boid::Boid;
+use crate::sim_params::{AlignmentMode, SimParams};
+use crate::vec2::Vec2;
+
+/// a single steering rule, the [Simulation](crate::simulation::Simulation) sums all of its behaviors
+///
+/// behaviors are shared between the steering threads, so they can't keep state per boid
+pub trait SteeringBehavior: Send + Sync {
+    /// the unweighted steering of `boid` towards its `neighbors`, which don't contain `boid` itself
+    ///
+    /// `index` and `neighbor_indices` are where `boid` and every one of `neighbors` are in the
+    /// [Simulation](crate::simulation::Simulation), they tell otherwise identical boids apart
+    fn steer(&self, boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], params: &SimParams) -> Vec2;
+
+    /// factor applied to [SteeringBehavior::steer]
+    fn weight(&self, params: &SimParams) -> f32;
+
+    /// shown when inspecting a boid
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+/// pushes apart from neighbors closer than [SimParams::separation_radius], leaders only get this one
+///
+/// the push falls off with [SimParams::separation_exponent] towards the radius
+pub struct Separation;
+
+/// heads the same way as the neighbors, closer ones count more with [SimParams::alignment_exponent]
+pub struct Alignment;
+
+/// pulls towards the center of the neighbors, closer ones count more with [SimParams::cohesion_exponent]
+///
+/// skipped below [SimParams::min_neighbors_for_cohesion] neighbors, like alignment below [SimParams::min_neighbors_for_alignment]
+pub struct Cohesion;
+
+impl SteeringBehavior for Separation {
+    fn steer(&self, boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], params: &SimParams) -> Vec2 {
+        // separating beyond the perception radius isn't possible, those neighbors are never found
+        let radius = params.separation_radius.min(params.perception_radius);
+
+        let mut separation = separation_sum(boid, index, neighbors, neighbor_indices, radius, params.separation_exponent);
+
+        // unlike alignment and cohesion this is a sum of pushes rather than an average, the divisor only keeps crowds
+        // from pushing far harder than a few neighbors. counting the boid itself keeps a single neighbor at half strength
+        separation.div(neighborhood_size(neighbors));
+        separation
+    }
+
+    fn weight(&self, params: &SimParams) -> f32 {
+        params.separation_weight
+    }
+
+    fn name(&self) -> &'static str {
+        "separation"
+    }
+}
+
+impl SteeringBehavior for Alignment {
+    fn steer(&self, boid: &Boid, _index: usize, neighbors: &[&Boid], _neighbor_indices: &[usize], params: &SimParams) -> Vec2 {
+        if boid.is_leader || neighbors.len() < params.min_neighbors_for_alignment {
+            return Vec2::ZERO;
+        }
+
+        let exponent = params.alignment_exponent;
+
+        let (mut alignment, weight) = match (params.alignment_mode, exponent) {
+            // the boids keep their vel normalized, it already is the heading
+            (AlignmentMode::Heading, 0) => (sum(neighbors, |neighbor_boid| &neighbor_boid.vel), neighbors.len() as f32),
+            (AlignmentMode::Heading, _) => weighted_sum(boid, neighbors, params.perception_radius, exponent, |neighbor_boid| neighbor_boid.vel.clone()),
+            (AlignmentMode::Velocity, _) => weighted_sum(boid, neighbors, params.perception_radius, exponent, |neighbor_boid| {
+                let mut vel = neighbor_boid.vel.clone();
+                vel.mul(neighbor_boid.speed);
+                vel
+            }),
+        };
+
+        if weight == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        // the average of the neighbors only like for cohesion, the boid's own heading isn't in the sum
+        alignment.div(weight);
+        alignment
+    }
+
+    fn weight(&self, params: &SimParams) -> f32 {
+        params.alignment_weight
+    }
+
+    fn name(&self) -> &'static str {
+        "alignment"
+    }
+}
+
+impl SteeringBehavior for Cohesion {
+    fn steer(&self, boid: &Boid, _index: usize, neighbors: &[&Boid], _neighbor_indices: &[usize], params: &SimParams) -> Vec2 {
+        if boid.is_leader || neighbors.len() < params.min_neighbors_for_cohesion {
+            return Vec2::ZERO;
+        }
+
+        let (mut cohesion, weight) = match params.cohesion_exponent {
+            0 => (sum(neighbors, |neighbor_boid| &neighbor_boid.location), neighbors.len() as f32),
+            exponent => weighted_sum(boid, neighbors, params.perception_radius, exponent, |neighbor_boid| neighbor_boid.location.clone()),
+        };
+
+        // without neighbors there is no center to head for
+        if weight == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        // only the neighbors make up the center. the boid's own location isn't in the sum, counting it in the
+        // divisor anyway would shrink the center towards the world origin
+        cohesion.div(weight);
+        cohesion.sub(&boid.location);
+        cohesion
+    }
+
+    fn weight(&self, params: &SimParams) -> f32 {
+        params.cohesion_weight
+    }
+
+    fn name(&self) -> &'static str {
+        "cohesion"
+    }
+}
+
+/// the summed up pushes of all `neighbors` closer than `radius`
+#[cfg(not(feature = "simd"))]
+fn separation_sum(boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], radius: f32, exponent: i32) -> Vec2 {
+    scalar_separation_sum(boid, index, neighbors, neighbor_indices, radius, exponent)
+}
+
+/// [scalar_separation_sum] on [LANES] neighbors at a time in vector registers
+///
+/// the sums are added up in a different order, so the result only matches the scalar one up to rounding
+#[cfg(feature = "simd")]
+fn separation_sum(boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], radius: f32, exponent: i32) -> Vec2 {
+    use wide::{CmpEq, CmpGt, CmpLt, f32x8};
+
+    let batches = neighbors.chunks_exact(LANES);
+    let index_batches = neighbor_indices.chunks_exact(LANES);
+    let mut separation = scalar_separation_sum(boid, index, batches.remainder(), index_batches.remainder(), radius, exponent);
+
+    let (x, y) = (f32x8::splat(boid.location.x), f32x8::splat(boid.location.y));
+    let radius_v = f32x8::splat(radius);
+
+    let mut sum_x = f32x8::ZERO;
+    let mut sum_y = f32x8::ZERO;
+
+    for (batch, index_batch) in batches.zip(index_batches) {
+        let dx = x - f32x8::new(std::array::from_fn(|lane| batch[lane].location.x));
+        let dy = y - f32x8::new(std::array::from_fn(|lane| batch[lane].location.y));
+        let mass = f32x8::new(std::array::from_fn(|lane| batch[lane].mass()));
+
+        let distance = (dx * dx + dy * dy).sqrt();
+        let falloff = powi((radius_v - distance) / radius_v, exponent);
+
+        // coincident neighbors and the ones beyond the radius are masked out, the division by 0 along with them
+        let in_range = distance.cmp_gt(f32x8::ZERO) & distance.cmp_lt(radius_v);
+        let strength = in_range.blend(falloff * mass / distance, f32x8::ZERO);
+
+        sum_x += dx * strength;
+        sum_y += dy * strength;
+
+        let coincident = distance.cmp_eq(f32x8::ZERO);
+
+        if coincident.any() {
+            for (lane, _) in coincident.to_array().iter().enumerate().filter(|(_, mask)| **mask != 0.0) {
+                separation.add(&coincident_push(boid, index, batch[lane], index_batch[lane]));
+            }
+        }
+    }
+
+    separation.add(&Vec2::new(sum_x.reduce_add(), sum_y.reduce_add()));
+    separation
+}
+
+/// `base` to the non negative `exponent` in every lane, by squaring
+#[cfg(feature = "simd")]
+fn powi(mut base: wide::f32x8, mut exponent: i32) -> wide::f32x8 {
+    let mut result = wide::f32x8::ONE;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result *= base;
+        }
+
+        base *= base;
+        exponent >>= 1;
+    }
+
+    result
+}
+
+/// neighbors handled at once by the vectorized sums, one AVX or two SSE registers of f32
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// one neighbor after another, the fallback of the vectorized [separation_sum]
+fn scalar_separation_sum(boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], radius: f32, exponent: i32) -> Vec2 {
+    let mut separation = Vec2::ZERO;
+
+    for (neighbor_boid, neighbor_index) in neighbors.iter().zip(neighbor_indices) {
+        let mut separation_vec = boid.location.clone();
+        separation_vec.sub(&neighbor_boid.location);
+
+        // the one sqrt per neighbor, used for both the falloff and the direction
+        let distance = separation_vec.length();
+
+        if distance == 0.0 {
+            separation.add(&coincident_push(boid, index, neighbor_boid, *neighbor_index));
+        } else if distance < radius {
+            // heavier neighbors push harder
+            let new_length = falloff(distance, radius, exponent) * neighbor_boid.mass();
+
+            separation_vec.mul(new_length / distance);
+            separation.add(&separation_vec);
+        }
+    }
+
+    separation
+}
+
+/// the push apart from a neighbor at exactly the same location, where the offset between them has no direction
+///
+/// the direction is picked from the shared location and the order of both boids in the simulation decides who goes which way,
+/// so the two always get opposite pushes, even when everything else about them is identical
+fn coincident_push(boid: &Boid, index: usize, neighbor_boid: &Boid, neighbor_index: usize) -> Vec2 {
+    let hash = (boid.location.x.to_bits() ^ boid.location.y.to_bits().rotate_left(16)).wrapping_mul(0x9e37_79b9);
+    let mut push = Vec2::from_angle(hash as f32 / u32::MAX as f32 * PI * 2.0);
+
+    if index < neighbor_index {
+        push.mul(-1.0);
+    }
+
+    // the full strength of the falloff at distance 0
+    push.mul(neighbor_boid.mass());
+    push
+}
+
+/// adds up `field` of all `neighbors`
+#[cfg(not(feature = "simd"))]
+fn sum(neighbors: &[&Boid], field: impl Fn(&Boid) -> &Vec2) -> Vec2 {
+    scalar_sum(neighbors, field)
+}
+
+/// [scalar_sum] on [LANES] neighbors at a time, the result only matches the scalar one up to rounding
+#[cfg(feature = "simd")]
+fn sum(neighbors: &[&Boid], field: impl Fn(&Boid) -> &Vec2) -> Vec2 {
+    use wide::f32x8;
+
+    let batches = neighbors.chunks_exact(LANES);
+    let mut total = scalar_sum(batches.remainder(), &field);
+
+    let mut sum_x = f32x8::ZERO;
+    let mut sum_y = f32x8::ZERO;
+
+    for batch in batches {
+        let values: [&Vec2; LANES] = std::array::from_fn(|lane| field(batch[lane]));
+
+        sum_x += f32x8::new(values.map(|value| value.x));
+        sum_y += f32x8::new(values.map(|value| value.y));
+    }
+
+    total.add(&Vec2::new(sum_x.reduce_add(), sum_y.reduce_add()));
+    total
+}
+
+/// two interleaved sums, so the additions don't all wait on each other. the fallback of the vectorized [sum]
+fn scalar_sum(neighbors: &[&Boid], field: impl Fn(&Boid) -> &Vec2) -> Vec2 {
+    let mut even = Vec2::ZERO;
+    let mut odd = Vec2::ZERO;
+
+    for pair in neighbors.chunks(2) {
+        even.add(field(pair[0]));
+
+        if let Some(neighbor_boid) = pair.get(1) {
+            odd.add(field(neighbor_boid));
+        }
+    }
+
+    even.add(&odd);
+    even
+}
+
+/// adds up `value` of all `neighbors`, each scaled by its [falloff] over `radius`, together with the summed up weights
+#[cfg(not(feature = "simd"))]
+fn weighted_sum(boid: &Boid, neighbors: &[&Boid], radius: f32, exponent: i32, value: impl Fn(&Boid) -> Vec2) -> (Vec2, f32) {
+    scalar_weighted_sum(boid, neighbors, radius, exponent, value)
+}
+
+/// [scalar_weighted_sum] on [LANES] neighbors at a time, the result only matches the scalar one up to rounding
+#[cfg(feature = "simd")]
+fn weighted_sum(boid: &Boid, neighbors: &[&Boid], radius: f32, exponent: i32, value: impl Fn(&Boid) -> Vec2) -> (Vec2, f32) {
+    use wide::f32x8;
+
+    let batches = neighbors.chunks_exact(LANES);
+    let (mut total, mut total_weight) = scalar_weighted_sum(boid, batches.remainder(), radius, exponent, &value);
+
+    let (x, y) = (f32x8::splat(boid.location.x), f32x8::splat(boid.location.y));
+    let radius_v = f32x8::splat(radius);
+
+    let mut sum_x = f32x8::ZERO;
+    let mut sum_y = f32x8::ZERO;
+    let mut sum_weight = f32x8::ZERO;
+
+    for batch in batches {
+        let weight = match exponent {
+            0 => f32x8::ONE,
+            _ => {
+                let dx = x - f32x8::new(std::array::from_fn(|lane| batch[lane].location.x));
+                let dy = y - f32x8::new(std::array::from_fn(|lane| batch[lane].location.y));
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                powi(((radius_v - distance) / radius_v).max(f32x8::ZERO), exponent)
+            }
+        };
+
+        let values: [Vec2; LANES] = std::array::from_fn(|lane| value(batch[lane]));
+
+        sum_x += f32x8::new(std::array::from_fn(|lane| values[lane].x)) * weight;
+        sum_y += f32x8::new(std::array::from_fn(|lane| values[lane].y)) * weight;
+        sum_weight += weight;
+    }
+
+    total.add(&Vec2::new(sum_x.reduce_add(), sum_y.reduce_add()));
+    total_weight += sum_weight.reduce_add();
+
+    (total, total_weight)
+}
+
+/// one neighbor after another, the fallback of the vectorized [weighted_sum]
+fn scalar_weighted_sum(boid: &Boid, neighbors: &[&Boid], radius: f32, exponent: i32, value: impl Fn(&Boid) -> Vec2) -> (Vec2, f32) {
+    let mut total = Vec2::ZERO;
+    let mut total_weight = 0.0;
+
+    for neighbor_boid in neighbors {
+        let weight = match exponent {
+            0 => 1.0,
+            _ => falloff(boid.location.distance_squared(&neighbor_boid.location).sqrt(), radius, exponent),
+        };
+
+        let mut neighbor_value = value(neighbor_boid);
+        neighbor_value.mul(weight);
+
+        total.add(&neighbor_value);
+        total_weight += weight;
+    }
+
+    (total, total_weight)
+}
+
+/// 1 at distance 0 down to 0 at `radius`, `exponent` sharpens the drop towards close distances
+///
+/// `exponent` must not be negative, see [SimParams::validate]
+fn falloff(distance: f32, radius: f32, exponent: i32) -> f32 {
+    ((radius - distance) / radius).max(0.0).powi(exponent)
+}
+
+/// the boid itself is part of the queried neighborhood, only used to scale [Separation]
+fn neighborhood_size(neighbors: &[&Boid]) -> f32 {
+    (neighbors.len() + 1) as f32
+}
+
+/// separation, alignment and cohesion, weighted by their [SimParams] weights
+pub fn default_behaviors() -> Vec<Arc<dyn SteeringBehavior>> {
+    vec![Arc::new(Separation), Arc::new(Alignment), Arc::new(Cohesion)]
+}
+
+/// the weighted sum of all `behaviors` for `boid` towards the given `neighbors`
+///
+/// `neighbors` must not contain `boid` itself, see [SteeringBehavior::steer] for the indices
+pub fn compute_steering(boid: &Boid, index: usize, neighbors: &[&Boid], neighbor_indices: &[usize], params: &SimParams, behaviors: &[Arc<dyn SteeringBehavior>]) -> Vec2 {
+    let mut steering = Vec2::ZERO;
+
+    for behavior in behaviors {
+        let mut force = behavior.steer(boid, index, neighbors, neighbor_indices, params);
+        force.mul(behavior.weight(params));
+
+        steering.add(&force);
+    }
+
+    steering
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boid(x: f32, y: f32, heading: f32) -> Boid {
+        Boid::new(Vec2::new(x, y), Vec2::from_angle(heading))
+    }
+
+    #[test]
+    fn neighbors_beyond_the_separation_radius_only_attract() {
+        let params = SimParams { perception_radius: 0.03, separation_radius: 0.01, ..SimParams::default() };
+        let neighbor_boid = boid(0.02, 0.0, 0.0);
+
+        let separation = Separation.steer(&boid(0.0, 0.0, 0.0), 0, &[&neighbor_boid], &[1], &params);
+        let cohesion = Cohesion.steer(&boid(0.0, 0.0, 0.0), 0, &[&neighbor_boid], &[1], &params);
+
+        assert_eq!(separation, Vec2::ZERO);
+        assert!(cohesion.x > 0.0, "{}", cohesion);
+    }
+
+    #[test]
+    fn opposite_headings_cancel_out() {
+        let neighbors = [boid(0.01, 0.0, 0.0), boid(-0.01, 0.0, PI)];
+        let neighbors: Vec<&Boid> = neighbors.iter().collect();
+
+        for alignment_mode in [AlignmentMode::Heading, AlignmentMode::Velocity] {
+            let params = SimParams { alignment_mode, ..SimParams::default() };
+            let alignment = Alignment.steer(&boid(0.0, 0.0, 0.5), 0, &neighbors, &[1, 2], &params);
+
+            assert!(alignment.length() < 1e-6, "{:?}: {}", alignment_mode, alignment);
+        }
+    }
+
+    #[test]
+    fn higher_exponents_favor_the_nearer_neighbor() {
+        let (near, far) = (boid(0.005, 0.0, 0.0), boid(0.0, 0.025, 0.0));
+        let mut previous_share = 0.0;
+
+        for cohesion_exponent in [0, 1, 3, 6] {
+            let params = SimParams { perception_radius: 0.03, cohesion_exponent, ..SimParams::default() };
+            let cohesion = Cohesion.steer(&boid(0.0, 0.0, 0.0), 0, &[&near, &far], &[1, 2], &params);
+
+            // the share of the near neighbor in the weighted center, the far one makes up the rest
+            let share = cohesion.x / near.location.x;
+            assert!((share + cohesion.y / far.location.y - 1.0).abs() < 1e-5, "{}", cohesion);
+
+            if cohesion_exponent == 0 {
+                assert!((share - 0.5).abs() < 1e-5, "unweighted share {}", share);
+            }
+
+            assert!(share > previous_share, "exponent {} gives {} after {}", cohesion_exponent, share, previous_share);
+            previous_share = share;
+        }
+    }
+
+    /// below, at and above a single batch of the vectorized sums, with and without a remainder
+    #[cfg(feature = "simd")]
+    const NEIGHBOR_COUNTS: [usize; 5] = [0, 3, 8, 13, 40];
+
+    /// scattered around the origin, some of them beyond a radius of 0.03 and the sixth one right at the origin
+    #[cfg(feature = "simd")]
+    fn random_neighbors(rng: &mut impl rand::Rng, count: usize) -> Vec<Boid> {
+        let mut neighbors: Vec<Boid> = (0..count)
+            .map(|_| {
+                let mut neighbor_boid = boid(rng.gen_range(-0.04..0.04), rng.gen_range(-0.04..0.04), rng.gen_range(0.0..PI * 2.0));
+                neighbor_boid.scale = rng.gen_range(0.5..1.5);
+                neighbor_boid.speed = rng.gen_range(0.0..1.0);
+                neighbor_boid
+            })
+            .collect();
+
+        if let Some(neighbor_boid) = neighbors.get_mut(5) {
+            neighbor_boid.location = Vec2::ZERO;
+        }
+
+        neighbors
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn vectorized_separation_matches_the_scalar_one() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha12Rng;
+
+        let mut rng = ChaCha12Rng::seed_from_u64(42);
+        let radius = 0.03;
+
+        for neighbor_count in NEIGHBOR_COUNTS {
+            for exponent in [0, 1, 3, 8] {
+                let neighbors = random_neighbors(&mut rng, neighbor_count);
+                let neighbors: Vec<&Boid> = neighbors.iter().collect();
+                let neighbor_indices: Vec<usize> = (1..=neighbor_count).collect();
+                let me = boid(0.0, 0.0, 0.0);
+
+                let vectorized = separation_sum(&me, 0, &neighbors, &neighbor_indices, radius, exponent);
+                let scalar = scalar_separation_sum(&me, 0, &neighbors, &neighbor_indices, radius, exponent);
+
+                assert!(vectorized.approx_eq(&scalar, 1e-5 * (1.0 + scalar.length())), "{} neighbors, exponent {}: {} and {}", neighbor_count, exponent, vectorized, scalar);
+            }
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn vectorized_sum_matches_the_scalar_one() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha12Rng;
+
+        let mut rng = ChaCha12Rng::seed_from_u64(42);
+
+        for neighbor_count in NEIGHBOR_COUNTS {
+            let neighbors = random_neighbors(&mut rng, neighbor_count);
+            let neighbors: Vec<&Boid> = neighbors.iter().collect();
+
+            // the fields summed up for alignment and cohesion
+            let sums = [
+                ("vel", sum(&neighbors, |neighbor_boid| &neighbor_boid.vel), scalar_sum(&neighbors, |neighbor_boid| &neighbor_boid.vel)),
+                ("location", sum(&neighbors, |neighbor_boid| &neighbor_boid.location), scalar_sum(&neighbors, |neighbor_boid| &neighbor_boid.location)),
+            ];
+
+            for (name, vectorized, scalar) in sums {
+                assert!(vectorized.approx_eq(&scalar, 1e-5 * (1.0 + scalar.length())), "{} neighbors, {}: {} and {}", neighbor_count, name, vectorized, scalar);
+            }
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn vectorized_weighted_sum_matches_the_scalar_one() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha12Rng;
+
+        let mut rng = ChaCha12Rng::seed_from_u64(42);
+        let radius = 0.03;
+        // the velocity alignment sums up the scaled vel, cohesion the location
+        let velocity = |neighbor_boid: &Boid| {
+            let mut vel = neighbor_boid.vel.clone();
+            vel.mul(neighbor_boid.speed);
+            vel
+        };
+        let location = |neighbor_boid: &Boid| neighbor_boid.location.clone();
+
+        for neighbor_count in NEIGHBOR_COUNTS {
+            for exponent in [0, 1, 3, 8] {
+                let neighbors = random_neighbors(&mut rng, neighbor_count);
+                let neighbors: Vec<&Boid> = neighbors.iter().collect();
+                let me = boid(0.0, 0.0, 0.0);
+
+                let sums = [
+                    ("velocity", weighted_sum(&me, &neighbors, radius, exponent, velocity), scalar_weighted_sum(&me, &neighbors, radius, exponent, velocity)),
+                    ("location", weighted_sum(&me, &neighbors, radius, exponent, location), scalar_weighted_sum(&me, &neighbors, radius, exponent, location)),
+                ];
+
+                for (name, (vectorized, vectorized_weight), (scalar, scalar_weight)) in sums {
+                    assert!(vectorized.approx_eq(&scalar, 1e-5 * (1.0 + scalar.length())), "{} neighbors, exponent {}, {}: {} and {}", neighbor_count, exponent, name, vectorized, scalar);
+                    assert!((vectorized_weight - scalar_weight).abs() <= 1e-5 * (1.0 + scalar_weight), "{} neighbors, exponent {}: weights {} and {}", neighbor_count, exponent, vectorized_weight, scalar_weight);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cohesion_points_at_the_center_of_the_neighbors() {
+        let neighbors = [boid(0.11, 0.2, 0.0), boid(0.1, 0.21, 0.0), boid(0.12, 0.22, 0.0)];
+        let neighbors: Vec<&Boid> = neighbors.iter().collect();
+        let me = boid(0.1, 0.2, 0.0);
+
+        let cohesion = Cohesion.steer(&me, 0, &neighbors, &[1, 2, 3], &SimParams::default());
+
+        // the center is at (0.11, 0.21), away from the world origin so shrinking it towards 0 would show
+        assert!(cohesion.approx_eq(&Vec2::new(0.01, 0.01), 1e-6), "{}", cohesion);
+    }
+
+    #[test]
+    fn too_few_neighbors_skip_cohesion() {
+        let params = SimParams { min_neighbors_for_cohesion: 2, ..SimParams::default() };
+        let (first, second) = (boid(0.02, 0.0, 0.0), boid(0.0, 0.02, 0.0));
+
+        let alone = Cohesion.steer(&boid(0.0, 0.0, 0.0), 0, &[&first], &[1], &params);
+        let enough = Cohesion.steer(&boid(0.0, 0.0, 0.0), 0, &[&first, &second], &[1, 2], &params);
+
+        assert_eq!(alone, Vec2::ZERO);
+        assert!(enough.approx_eq(&Vec2::new(0.01, 0.01), 1e-6), "{}", enough);
+    }
+}
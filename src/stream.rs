@@ -0,0 +1,130 @@
+use std::io;
+use std::io::{BufWriter, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use instant::Instant;
+
+use crate::readback::Frame;
+
+pub const DEFAULT_STREAM_FPS: f32 = 30.0;
+
+/// longest run of identical pixels in a single run, the count is a single byte
+const MAX_RUN: usize = u8::MAX as usize;
+
+/// serves rendered frames to one connected viewer at a time
+///
+/// every frame is `width`, `height` and `length` as little endian u32, followed by `length` bytes of
+/// run length encoded RGBA pixels. each run is a count byte and the 4 bytes of the repeated pixel
+pub struct FrameStream {
+    /// holds at most one frame, frames arriving while the last one is still being sent are dropped
+    sender: SyncSender<Frame>,
+    /// a viewer is connected, no frames are captured otherwise
+    connected: Arc<AtomicBool>,
+    interval: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl FrameStream {
+    /// listens on `address` and sends at most `fps` frames per second
+    pub fn bind(address: &str, fps: f32) -> io::Result<FrameStream> {
+        let listener = TcpListener::bind(address)?;
+        log::info!("streaming frames on {}", listener.local_addr()?);
+
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let connected = Arc::new(AtomicBool::new(false));
+
+        let thread_connected = connected.clone();
+        thread::spawn(move || serve(listener, receiver, thread_connected));
+
+        Ok(FrameStream {
+            sender,
+            connected,
+            interval: Duration::from_secs_f32(1.0 / fps),
+            last_frame: None,
+        })
+    }
+
+    /// whether the current frame should be captured, only while a viewer is connected and at most at the target framerate
+    pub fn wants_frame(&mut self) -> bool {
+        if !self.connected.load(Ordering::Relaxed) || self.last_frame.is_some_and(|last_frame| last_frame.elapsed() < self.interval) {
+            return false;
+        }
+
+        self.last_frame = Some(Instant::now());
+        true
+    }
+
+    pub fn send(&self, frame: Frame) {
+        // the viewer is too slow, it just gets a lower framerate
+        let _ = self.sender.try_send(frame);
+    }
+}
+
+/// accepts viewers one after another until the [FrameStream] gets dropped
+fn serve(listener: TcpListener, receiver: Receiver<Frame>, connected: Arc<AtomicBool>) {
+    for client in listener.incoming() {
+        let client = match client {
+            Ok(client) => client,
+            Err(err) => {
+                log::warn!("couldn't accept a viewer: {}", err);
+                continue;
+            }
+        };
+
+        log::info!("streaming to {}", client.peer_addr().map(|address| address.to_string()).unwrap_or_default());
+
+        // frames captured for the last viewer are stale by now
+        while receiver.try_recv().is_ok() {}
+        connected.store(true, Ordering::Relaxed);
+
+        let result = send_frames(client, &receiver);
+        connected.store(false, Ordering::Relaxed);
+
+        match result {
+            Ok(()) => return,
+            Err(err) => log::info!("viewer disconnected: {}", err),
+        }
+    }
+}
+
+/// sends frames until the viewer disconnects, [Ok] once the [FrameStream] is gone
+fn send_frames(client: TcpStream, receiver: &Receiver<Frame>) -> io::Result<()> {
+    client.set_nodelay(true)?;
+    let mut writer = BufWriter::new(client);
+
+    while let Ok(frame) = receiver.recv() {
+        let encoded = encode_rle(&frame.rgba);
+
+        writer.write_all(&frame.width.to_le_bytes())?;
+        writer.write_all(&frame.height.to_le_bytes())?;
+        writer.write_all(&(encoded.len() as u32).to_le_bytes())?;
+        writer.write_all(&encoded)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// runs of identical RGBA pixels as a count byte followed by the pixel
+pub fn encode_rle(rgba: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut pixels = rgba.chunks_exact(4).peekable();
+
+    while let Some(pixel) = pixels.next() {
+        let mut run = 1;
+
+        while run < MAX_RUN && pixels.next_if_eq(&pixel).is_some() {
+            run += 1;
+        }
+
+        encoded.push(run as u8);
+        encoded.extend_from_slice(pixel);
+    }
+
+    encoded
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sim_params::SimParams;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub frame: u64,
+    pub params: SimParams,
+}
+
+/// parameters changing over time, interpolated linearly between keyframes
+///
+/// loaded from a json list of keyframes like `[{"frame": 0, "params": {"cohesion_weight": 0.0}}, ...]`
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    /// sorted by frame, never empty
+    keyframes: Vec<Keyframe>,
+}
+
+impl Timeline {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Option<Timeline> {
+        if keyframes.is_empty() {
+            return None;
+        }
+
+        keyframes.sort_by_key(|keyframe| keyframe.frame);
+
+        Some(Timeline { keyframes })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Timeline> {
+        let reader = BufReader::new(File::open(path)?);
+        let keyframes: Vec<Keyframe> = serde_json::from_reader(reader)?;
+
+        for keyframe in &keyframes {
+            keyframe.params.validate()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("keyframe at frame {}: {}", keyframe.frame, err)))?;
+        }
+
+        Timeline::new(keyframes).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the timeline has no keyframes"))
+    }
+
+    /// the parameters at `frame`, the world and boundary are kept from `current`
+    ///
+    /// before the first and after the last keyframe the parameters of that keyframe are used
+    pub fn params_at(&self, frame: u64, current: &SimParams) -> SimParams {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.frame <= frame);
+
+        let params = if next == 0 {
+            self.keyframes[0].params.clone()
+        } else if next == self.keyframes.len() {
+            self.keyframes[next - 1].params.clone()
+        } else {
+            let from = &self.keyframes[next - 1];
+            let to = &self.keyframes[next];
+            let t = (frame - from.frame) as f32 / (to.frame - from.frame) as f32;
+
+            from.params.lerp(&to.params, t)
+        };
+
+        SimParams {
+            world_bounds: current.world_bounds.clone(),
+            boundary: current.boundary.clone(),
+            ..params
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::boid::Boid;
+use crate::vec2::Vec2;
+
+pub const DEFAULT_TRAIL_LENGTH: usize = 12;
+pub const MAX_TRAIL_LENGTH: usize = 32;
+
+/// the last few positions of every boid, kept in one ring buffer per boid
+///
+/// all trails live in a single allocation, 20000 boids with 16 positions each take about 2.5MB
+#[derive(Debug, Clone)]
+pub struct Trails {
+    length: usize,
+    /// `length` slots per boid, boid `i` owns `i * length..(i + 1) * length`
+    positions: Vec<Vec2>,
+    /// the slot written next, the same for every boid
+    head: usize,
+    /// how many slots hold a position, grows until it reaches `length`
+    filled: usize,
+}
+
+impl Trails {
+    pub fn new(length: usize) -> Trails {
+        Trails {
+            length: length.max(1),
+            positions: Vec::new(),
+            head: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// forgets all positions, e.g. after the boids were replaced
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.head = 0;
+        self.filled = 0;
+    }
+
+    /// appends the current position of every boid, a changed boid count starts all trails over
+    pub fn record(&mut self, boids: &[Boid]) {
+        if self.positions.len() != boids.len() * self.length {
+            self.positions = vec![Vec2::ZERO; boids.len() * self.length];
+            self.head = 0;
+            self.filled = 0;
+        }
+
+        for (index, boid) in boids.iter().enumerate() {
+            self.positions[index * self.length + self.head] = boid.location.clone();
+        }
+
+        self.head = (self.head + 1) % self.length;
+        self.filled = (self.filled + 1).min(self.length);
+    }
+
+    /// the recorded positions of boid `index`, newest first
+    pub fn trail(&self, index: usize) -> impl Iterator<Item=&Vec2> {
+        // nothing is recorded for boids added since the last record
+        let slots = self.positions.get(index * self.length..(index + 1) * self.length).unwrap_or(&[]);
+        let filled = if slots.is_empty() { 0 } else { self.filled };
+        let newest = self.head + self.length - 1;
+
+        (0..filled).map(move |age| &slots[(newest - age) % self.length])
+    }
+}
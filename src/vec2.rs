@@ -1,10 +1,33 @@
-#[derive(Debug, Clone)]
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
 }
 
+impl Default for Vec2 {
+    fn default() -> Self {
+        Vec2::ZERO
+    }
+}
+
+/// formats as `(x, y)`, a precision like `{:.3}` applies to both components
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "({:.*}, {:.*})", precision, self.x, precision, self.y),
+            None => write!(f, "({}, {})", self.x, self.y),
+        }
+    }
+}
+
 impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+    pub const UNIT_X: Vec2 = Vec2 { x: 1.0, y: 0.0 };
+    pub const UNIT_Y: Vec2 = Vec2 { x: 0.0, y: 1.0 };
+
     pub fn new(x: f32, y: f32) -> Vec2 {
         Vec2 {
             x,
@@ -12,6 +35,11 @@ impl Vec2 {
         }
     }
 
+    /// alias of [Vec2::new]
+    pub fn from_components(x: f32, y: f32) -> Vec2 {
+        Vec2::new(x, y)
+    }
+
     pub fn from_angle(angle: f32) -> Vec2 {
         Vec2 {
             x: angle.cos(),
@@ -19,8 +47,22 @@ impl Vec2 {
         }
     }
 
+    /// whether both components differ by at most `epsilon`
+    pub fn approx_eq(&self, other: &Vec2, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon
+    }
+
     pub fn length(&self) -> f32 {
-        (self.x.powi(2) + self.y.powi(2)).sqrt()
+        self.length_squared().sqrt()
+    }
+
+    /// cheaper than [Vec2::length] when only comparing lengths
+    pub fn length_squared(&self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn distance_squared(&self, other: &Vec2) -> f32 {
+        (self.x - other.x).powi(2) + (self.y - other.y).powi(2)
     }
 
     pub fn normalize(&mut self) {
@@ -54,7 +96,12 @@ impl Vec2 {
         self.y /= factor;
     }
 
-    pub fn angle(&mut self) -> f32 {
+    pub fn angle(&self) -> f32 {
         self.y.atan2(self.x)
     }
+
+    /// the point `t` of the way from `self` at 0 to `other` at 1
+    pub fn lerp(&self, other: &Vec2, t: f32) -> Vec2 {
+        Vec2::new(self.x + (other.x - self.x) * t, self.y + (other.y - self.y) * t)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+use boids::boid::{Boid, SIZE};
+use boids::sim_params::{BoundaryMode, SimParams, SpawnPattern};
+use boids::simulation::Simulation;
+use boids::vec2::Vec2;
+
+const SEED: u64 = 42;
+
+fn boid(x: f32, y: f32, heading: f32) -> Boid {
+    Boid::new(Vec2::new(x, y), Vec2::from_angle(heading))
+}
+
+#[test]
+fn stacked_boids_get_pushed_apart() {
+    let mut stacked = boid(0.1, 0.1, 0.0);
+    stacked.scale = 1.5;
+
+    let mut simulation = Simulation::new_deterministic(vec![boid(0.1, 0.1, 0.0), stacked], SimParams::default(), SEED);
+    simulation.resolve_collisions();
+
+    let boids = simulation.boids();
+    let distance = boids[0].location.distance_squared(&boids[1].location).sqrt();
+
+    assert!(distance >= SIZE * (boids[0].scale + boids[1].scale), "{} apart", distance);
+}
+
+#[test]
+fn thread_count_does_not_change_the_result() {
+    let run = |thread_count: usize| {
+        let mut simulation = Simulation::new(500, SimParams::default(), SEED);
+        simulation.thread_count = thread_count;
+        // the threads would only be used from 2000 boids on
+        simulation.parallel_threshold = 0;
+
+        for _ in 0..100 {
+            simulation.update();
+        }
+
+        simulation.boids().iter()
+            .map(|boid| (boid.location.clone(), boid.vel.clone(), boid.speed))
+            .collect::<Vec<_>>()
+    };
+
+    let sequential = run(1);
+
+    for thread_count in [2, 8] {
+        assert!(run(thread_count) == sequential, "{} threads diverged", thread_count);
+    }
+}
+
+#[test]
+fn coincident_boids_move_apart() {
+    // without jitter nothing but the separation tells the two apart
+    let params = SimParams { jitter: 0.0, ..SimParams::default() };
+    let mut simulation = Simulation::new_deterministic(vec![boid(0.2, -0.1, 1.0), boid(0.2, -0.1, 1.0)], params, SEED);
+
+    for _ in 0..5 {
+        simulation.update();
+    }
+
+    let boids = simulation.boids();
+    let distance = boids[0].location.distance_squared(&boids[1].location).sqrt();
+
+    assert!(distance > SIZE, "only {} apart", distance);
+}
+
+/// keeps the steering from the rules alone
+fn calm_params() -> SimParams {
+    SimParams { jitter: 0.0, ..SimParams::default() }
+}
+
+fn heading_difference(a: &Boid, b: &Boid) -> f32 {
+    let difference = (a.vel.angle() - b.vel.angle()).abs();
+    difference.min(std::f32::consts::TAU - difference)
+}
+
+#[test]
+fn two_boids_align_their_headings() {
+    let mut simulation = Simulation::new_deterministic(vec![boid(0.0, 0.0, 0.0), boid(0.0, 0.015, 1.0)], calm_params(), SEED);
+    let start = heading_difference(&simulation.boids()[0], &simulation.boids()[1]);
+
+    for _ in 0..10 {
+        simulation.update();
+    }
+
+    let end = heading_difference(&simulation.boids()[0], &simulation.boids()[1]);
+    assert!(end < start / 2.0, "from {} to {}", start, end);
+}
+
+#[test]
+fn a_tight_line_spreads_out_evenly() {
+    let line = (0..5).map(|i| boid((i as f32 - 2.0) * 0.004, 0.0, std::f32::consts::FRAC_PI_2)).collect();
+    let mut simulation = Simulation::new_deterministic(line, calm_params(), SEED);
+
+    for _ in 0..20 {
+        simulation.update();
+    }
+
+    let (min, max) = simulation.bounds();
+    let centroid = simulation.centroid().unwrap();
+
+    assert!(max.x - min.x > 0.016, "only {} wide", max.x - min.x);
+    // the line is symmetric, so nothing pulls it sideways
+    assert!(centroid.x.abs() < 1e-5, "drifted to {}", centroid.x);
+}
+
+#[test]
+fn a_ring_is_pulled_towards_its_center() {
+    let ring = (0..8)
+        .map(|i| {
+            let angle = i as f32 / 8.0 * std::f32::consts::TAU;
+            let mut location = Vec2::from_angle(angle);
+            location.mul(0.01);
+
+            boid(location.x, location.y, angle + std::f32::consts::FRAC_PI_2)
+        })
+        .collect();
+    let simulation = Simulation::new_deterministic(ring, calm_params(), SEED);
+
+    for index in 0..8 {
+        let inspection = simulation.inspect(index).unwrap();
+        let location = &simulation.boids()[index].location;
+
+        assert_eq!(inspection.neighbors.len(), 7);
+
+        let [separation, _, cohesion] = &inspection.steering[..] else {
+            panic!("expected the default behaviors");
+        };
+
+        // outwards and inwards along the radius
+        assert!(separation.x * location.x + separation.y * location.y > 0.0, "separation {} at {}", separation, location);
+        assert!(cohesion.x * location.x + cohesion.y * location.y < 0.0, "cohesion {} at {}", cohesion, location);
+    }
+}
+
+#[test]
+fn deaths_and_births_in_the_same_frame_drop_the_index() {
+    // as many are born as die every frame, so the boid count never changes
+    let params = SimParams { max_age: Some(50), birth_rate: 10.0, max_population: 500, ..SimParams::default() };
+    let mut simulation = Simulation::new(500, params, SEED);
+
+    let boids = simulation.boids().iter().cloned().enumerate()
+        .map(|(i, mut boid)| {
+            boid.age = i as u32 % 50;
+            boid
+        })
+        .collect();
+    simulation.set_boids(boids);
+
+    for _ in 0..10 {
+        simulation.update();
+        assert_eq!(simulation.boids().len(), 500);
+
+        // an index still holding the dead boids would point at whichever boid took their place,
+        // a current one is off by at most the step both boids took since
+        for boid in simulation.boids() {
+            let nearest = &simulation.boids()[simulation.nearest_boid(&boid.location).unwrap()];
+            let distance = nearest.location.distance_squared(&boid.location).sqrt();
+
+            assert!(distance <= 2.0 * simulation.params.max_speed, "the nearest boid is {} away", distance);
+        }
+    }
+}
+
+#[test]
+fn a_restored_snapshot_continues_like_the_original() {
+    // spread over the whole world, so some boids are near the edges
+    let params = SimParams { birth_rate: 0.35, spawn_pattern: SpawnPattern::ColorBands, ..SimParams::default() };
+    let mut original = Simulation::new(300, params.clone(), SEED);
+
+    for _ in 0..20 {
+        original.update();
+    }
+
+    // halfway into the switch, with a fraction of a birth saved up
+    original.params.boundary_mode = BoundaryMode::Wrap;
+
+    for _ in 0..30 {
+        original.update();
+    }
+
+    let mut restored = Simulation::new(0, SimParams { boundary_mode: BoundaryMode::Wrap, ..params }, SEED + 1);
+    restored.restore(&original.snapshot());
+
+    let state = |simulation: &Simulation| {
+        simulation.boids().iter()
+            .map(|boid| (boid.location.clone(), boid.vel.clone(), boid.speed, boid.age))
+            .collect::<Vec<_>>()
+    };
+
+    for frame in 0..60 {
+        original.update();
+        restored.update();
+
+        assert!(state(&restored) == state(&original), "diverged in frame {}", frame);
+    }
+}
@@ -0,0 +1,39 @@
+// ignored as they take minutes, run them with `cargo test --release --test stress -- --ignored`
+
+use boids::sim_params::SimParams;
+use boids::simulation::Simulation;
+
+const SEED: u64 = 42;
+/// boids get pushed back once past the world border, but not instantly
+const BOUNDS_SLACK: f32 = 1.1;
+
+/// steps a seeded simulation and checks every boid after every frame
+fn assert_stays_finite_and_inside(params: SimParams, boid_count: usize, frames: usize) {
+    let mut simulation = Simulation::new(boid_count, params, SEED);
+    let world_bounds = simulation.params.world_bounds.clone();
+
+    for frame in 0..frames {
+        simulation.update();
+
+        for (index, boid) in simulation.boids().iter().enumerate() {
+            let finite = boid.location.x.is_finite() && boid.location.y.is_finite() && boid.vel.x.is_finite() && boid.vel.y.is_finite() && boid.speed.is_finite();
+            assert!(finite, "boid {} in frame {} at {} moving {} at {}", index, frame, boid.location, boid.vel, boid.speed);
+
+            let inside = boid.location.x.abs() <= world_bounds.x * BOUNDS_SLACK && boid.location.y.abs() <= world_bounds.y * BOUNDS_SLACK;
+            assert!(inside, "boid {} in frame {} at {} left the world", index, frame, boid.location);
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn boids_stay_finite_and_inside_the_world() {
+    assert_stays_finite_and_inside(SimParams::default(), 5000, 5000);
+}
+
+#[test]
+#[ignore]
+fn boids_stay_finite_without_an_edge_margin() {
+    // the push back used to divide by the margin
+    assert_stays_finite_and_inside(SimParams { edge_margin: 0.0, ..SimParams::default() }, 1000, 1000);
+}